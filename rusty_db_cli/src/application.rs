@@ -12,6 +12,7 @@ use tokio::{task::JoinHandle, time::sleep};
 use crate::{
     managers::{event_manager::Event, window_manager::WindowManager},
     ui::window::{OnInputInfo, WindowRenderInfo},
+    utils::keymap::{Action, KEYMAP},
     widgets::throbber::{get_throbber_data, Throbber},
 };
 
@@ -76,6 +77,10 @@ impl App {
     }
 
     pub fn on_key(&mut self, key: event::KeyEvent) {
+        let is_capturing_input = self
+            .window_manager
+            .get_focused_window()
+            .is_capturing_input();
         self.window_manager
             .get_focused_window()
             .on_key(Event::OnInput(OnInputInfo {
@@ -83,13 +88,16 @@ impl App {
                 mode: self.mode,
                 key,
             }));
+        if is_capturing_input {
+            return;
+        }
 
         match self.mode {
-            Mode::View => match key.code {
-                event::KeyCode::Char('q') => {
+            Mode::View => match KEYMAP.get_action(key.code) {
+                Some(Action::Quit) => {
                     self.should_exit = true;
                 }
-                event::KeyCode::Char(':') => {
+                Some(Action::OpenCommandLine) => {
                     self.set_mode(Mode::Input);
                 }
                 _ => {}
@@ -105,6 +113,23 @@ impl App {
     }
 }
 
+impl App {
+    /// Movement of the mouse is reported too, only the wheel and clicks are passed on
+    pub fn on_mouse(&mut self, mouse: event::MouseEvent) {
+        let is_handled = matches!(
+            mouse.kind,
+            event::MouseEventKind::ScrollDown
+                | event::MouseEventKind::ScrollUp
+                | event::MouseEventKind::Down(event::MouseButton::Left)
+        );
+        if !is_handled || !matches!(self.mode, Mode::View) {
+            return;
+        }
+
+        self.window_manager.get_focused_window().on_mouse(mouse);
+    }
+}
+
 #[macro_export]
 macro_rules! log_error {
     ($event_sender:expr, $err:expr) => {
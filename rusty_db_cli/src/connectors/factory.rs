@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use tokio::sync::Mutex;
+
+use super::{
+    base::{retry, Connector},
+    mongodb::connector::MongodbConnectorBuilder,
+};
+
+/// Database the connection uri points to, selected by its scheme
+pub enum Backend {
+    Mongodb,
+}
+
+pub fn get_backend(uri: &str) -> Result<Backend> {
+    let Some((scheme, _)) = uri.split_once("://") else {
+        bail!("Connection uri has to start with a scheme, e.g. mongodb://");
+    };
+
+    match scheme.to_ascii_lowercase().as_str() {
+        "mongodb" | "mongodb+srv" => Ok(Backend::Mongodb),
+        "postgres" | "postgresql" => bail!("PostgreSQL is not supported yet"),
+        scheme => bail!(
+            "Unknown scheme '{}', expected mongodb or mongodb+srv",
+            scheme
+        ),
+    }
+}
+
+/// Connects to the database of the uri, network errors are retried with `on_retry` called
+/// before each retry. `password` replaces the placeholder of the uri
+pub async fn build_connector(
+    uri: &str,
+    password: Option<String>,
+    on_retry: impl Fn(u32, &anyhow::Error),
+) -> Result<Arc<Mutex<dyn Connector>>> {
+    match get_backend(uri)? {
+        Backend::Mongodb => {
+            let connector = retry(
+                || {
+                    MongodbConnectorBuilder::new(uri)
+                        .with_password(password.clone())
+                        .build()
+                },
+                on_retry,
+            )
+            .await?;
+            Ok(Arc::new(Mutex::new(connector)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_is_selected_by_scheme() {
+        assert!(matches!(
+            get_backend("mongodb://localhost:27017"),
+            Ok(Backend::Mongodb)
+        ));
+        assert!(matches!(
+            get_backend("MONGODB+SRV://cluster.example.com/test"),
+            Ok(Backend::Mongodb)
+        ));
+        assert!(get_backend("postgresql://localhost/test").is_err());
+        assert!(get_backend("mysql://localhost/test").is_err());
+        // Not enough to contain the name of the backend
+        assert!(get_backend("localhost/mongodb").is_err());
+    }
+}
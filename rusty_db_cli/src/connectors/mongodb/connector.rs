@@ -1,11 +1,25 @@
-use std::{fs::File, io::Write, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use chrono::TimeZone;
 use mongodb::{
-    bson::{doc, from_document, to_bson, Bson, Document},
-    options::{AggregateOptions, ClientOptions, DistinctOptions, FindOptions},
+    bson::{
+        doc, from_document, oid::ObjectId, spec::BinarySubtype, to_bson, to_document, Binary, Bson,
+        Document, Uuid,
+    },
+    change_stream::ChangeStream,
+    error::{BulkWriteFailure, ErrorKind, WriteFailure},
+    options::{
+        AggregateOptions, ChangeStreamOptions, ClientOptions, CountOptions, CursorType,
+        DistinctOptions, FindOptions, FullDocumentType, IndexOptions, ReadConcern, ReplaceOptions,
+        SelectionCriteria, UpdateModifications, UpdateOptions,
+    },
     results::CollectionSpecification,
     Client, Collection, Cursor, Database, IndexModel,
 };
@@ -19,17 +33,32 @@ use rusty_db_cli_mongo::{
 };
 use serde_json::Map;
 
-use super::interpreter::InterpreterMongo;
+use super::{interpreter::InterpreterMongo, schema::infer_schema};
 use crate::{
     connectors::base::{
-        Connector, ConnectorInfo, DatabaseData, DatabaseValue, Object, PaginationInfo,
+        get_page_size, Connector, ConnectorInfo, DatabaseData, DatabaseValue, Object, OnBatch,
+        PaginationInfo, TransientError,
     },
     try_from,
-    utils::external_editor::{DEBUG_FILE, MONGO_COLLECTIONS_FILE},
+    ui::layouts::CLI_ARGS,
+    utils::{
+        external_editor::{
+            get_collections_file, write_file_atomically, DEBUG_FILE, MONGO_COLLECTIONS_FILE,
+        },
+        password::PASSWORD_PLACEHOLDER,
+    },
 };
 
+/// Documents the fields and types of a collection are inferred from by `describe`
+const DESCRIBE_SAMPLE_SIZE: i32 = 100;
+
+/// Collections listed for the completion are listed again by the first query after this long
+const COLLECTIONS_TTL: Duration = Duration::from_secs(5 * 60);
+
 pub struct MongodbConnectorBuilder {
     info: Option<ConnectorInfo>,
+    /// Typed in by the user, replaces the placeholder in the uri
+    password: Option<String>,
 }
 
 impl MongodbConnectorBuilder {
@@ -40,12 +69,20 @@ impl MongodbConnectorBuilder {
                 host: "unknown".to_string(),
                 database: "unknown".to_string(),
             }),
+            password: None,
         }
     }
 
+    pub fn with_password(mut self, password: Option<String>) -> Self {
+        self.password = password;
+        self
+    }
+
     pub async fn build(self) -> Result<MongodbConnector> {
         let mut info = self.info.unwrap();
-        let client_opts = ClientOptions::parse(info.uri.clone()).await?;
+        let mut client_opts = ClientOptions::parse(info.uri.clone()).await?;
+        set_timeouts(&mut client_opts);
+        set_password(&mut client_opts, self.password)?;
         let client = Client::with_options(client_opts.clone())?;
 
         if !client_opts.hosts.is_empty() {
@@ -57,18 +94,15 @@ impl MongodbConnectorBuilder {
         let collections = client
             .database(&database)
             .list_collection_names(None)
-            .await?
-            .iter()
-            .fold(String::new(), |acc, name| acc + name + "\n");
-
-        let mut file = File::create(MONGO_COLLECTIONS_FILE.to_string()).unwrap();
-        file.write_all(collections.as_bytes())?;
-        file.flush()?;
+            .await
+            .map_err(to_connector_error)?;
+        write_collections(&info.host, &database, &collections)?;
 
         Ok(MongodbConnector {
             info,
             client,
             database,
+            collections_listed_at: Mutex::new(Instant::now()),
         })
     }
 }
@@ -77,8 +111,11 @@ pub struct MongodbConnector {
     info: ConnectorInfo,
     pub client: Client,
     pub database: String,
+    /// Collections of the database are listed again once they're older than the TTL
+    collections_listed_at: Mutex<Instant>,
 }
 
+// Names are listed in `COMMANDS` of the standard library for the LSP diagnostics
 impl TryFrom<(String, ParametersExpression)> for Command {
     type Error = InterpreterError;
 
@@ -89,6 +126,92 @@ impl TryFrom<(String, ParametersExpression)> for Command {
         ));
         match command.to_lowercase().as_str() {
             "getindexes" => Ok(Command::GetIndexes(GetIndexesQuery)),
+            "drop" => {
+                if !params.params.is_empty() {
+                    return Err(InterpreterError {
+                        message: "Drop command doesn't accept any parameter".to_string(),
+                    });
+                }
+                ensure_destructive_allowed(&command)?;
+
+                Ok(Command::Drop(DropQuery))
+            }
+            "stats" => {
+                if params.params.len() > 1 {
+                    return Err(InterpreterError {
+                        message: "Stats only accepts 1 parameter".to_string(),
+                    });
+                }
+
+                let options = match params.params.first() {
+                    Some(Identifier::Object(options)) => {
+                        Some(to_interpter_error!(to_document(options))?)
+                    }
+                    Some(_) => {
+                        return Err(InterpreterError {
+                            message: "Stats options must be an object".to_string(),
+                        })
+                    }
+                    None => None,
+                };
+
+                Ok(Command::Stats(StatsQuery { options }))
+            }
+            "createindex" => {
+                ensure_write_allowed(&command)?;
+                if params.params.len() > 2 {
+                    return Err(InterpreterError {
+                        message: "CreateIndex only accepts 2 parameters".to_string(),
+                    });
+                }
+
+                let keys = params.get_nth_of_type::<ObjectExpression>(0)?;
+                if keys.properties.is_empty() {
+                    return Err(InterpreterError {
+                        message: "CreateIndex requires at least one key".to_string(),
+                    });
+                }
+                let keys = to_interpter_error!(to_document(&keys))?;
+
+                let options = match params.params.get(1) {
+                    Some(Identifier::Object(options)) => Some(to_interpter_error!(
+                        from_document::<IndexOptions>(to_interpter_error!(to_document(options))?)
+                    )?),
+                    Some(_) => {
+                        return Err(InterpreterError {
+                            message: "CreateIndex options must be an object".to_string(),
+                        })
+                    }
+                    None => None,
+                };
+
+                Ok(Command::CreateIndex(CreateIndexQuery {
+                    index: IndexModel::builder().keys(keys).options(options).build(),
+                }))
+            }
+            "dropindex" => {
+                if params.params.len() != 1 {
+                    return Err(InterpreterError {
+                        message: "DropIndex requires exactly 1 parameter".to_string(),
+                    });
+                }
+                ensure_destructive_allowed(&command)?;
+
+                let index = params.get_nth_of_type::<Identifier>(0)?;
+                let index = match index {
+                    Identifier::Literal(Literal::String(name)) => Bson::String(name),
+                    Identifier::Object(keys) => {
+                        Bson::Document(to_interpter_error!(to_document(&keys))?)
+                    }
+                    _ => {
+                        return Err(InterpreterError {
+                            message: "DropIndex only accepts index name or keys object".to_string(),
+                        })
+                    }
+                };
+
+                Ok(Command::DropIndex(DropIndexQuery { index }))
+            }
             "find" => {
                 if params.params.len() > 2 {
                     return Err(InterpreterError {
@@ -106,6 +229,8 @@ impl TryFrom<(String, ParametersExpression)> for Command {
 
                 if filter.is_some() && !filter.as_ref().unwrap().properties.is_empty() {
                     if let Bson::Document(doc) = to_interpter_error!(to_bson(&filter))? {
+                        let doc = parse_extended_json(doc)?;
+                        validate_filter(&doc, "")?;
                         return Ok(Command::Find(FindQuery {
                             options: opts,
                             filter: Some(doc),
@@ -124,6 +249,8 @@ impl TryFrom<(String, ParametersExpression)> for Command {
 
                 if filter.is_some() && !filter.as_ref().unwrap().properties.is_empty() {
                     if let Bson::Document(doc) = to_interpter_error!(to_bson(&filter))? {
+                        let doc = parse_extended_json(doc)?;
+                        validate_filter(&doc, "")?;
                         return Ok(Command::Count(CountQuery {
                             filter: Some(doc),
                             ..Default::default()
@@ -154,6 +281,14 @@ impl TryFrom<(String, ParametersExpression)> for Command {
                     .map(|p| {
                         let object = try_from!(<ObjectExpression>(p))?;
                         if let Bson::Document(doc) = to_interpter_error!(to_bson(&object))? {
+                            let doc = parse_extended_json(doc)?;
+                            doc.values().try_for_each(validate_system_variables)?;
+                            if let Ok(filter) = doc.get_document("$match") {
+                                validate_filter(filter, "$match")?;
+                            }
+                            if let Some(lookup) = doc.get("$lookup") {
+                                validate_lookup(lookup, "$lookup")?;
+                            }
                             Ok(doc)
                         } else {
                             Err(InterpreterError {
@@ -163,12 +298,16 @@ impl TryFrom<(String, ParametersExpression)> for Command {
                     })
                     .collect::<Result<Vec<Document>, InterpreterError>>()?;
 
+                if let Some(stage) = pipelines.iter().find_map(get_write_stage) {
+                    ensure_destructive_allowed(&format!("{} with {} stage", command, stage))?;
+                }
+
                 Ok(Command::Aggregate(AggregateQuery {
                     pipelines,
                     options: AggregateOptions::default(),
                     limit: None,
                     skip: None,
-                    explain: false,
+                    explain: None,
                 }))
             }
             "distinct" => {
@@ -190,7 +329,12 @@ impl TryFrom<(String, ParametersExpression)> for Command {
                     .and_then(|bson| match bson {
                         Bson::Document(doc) => Some(doc),
                         _ => None,
-                    });
+                    })
+                    .map(parse_extended_json)
+                    .transpose()?;
+                if let Some(filter) = &filter {
+                    validate_filter(filter, "")?;
+                }
 
                 let opts_values = params
                     .get_nth_of_type::<ObjectExpression>(2)
@@ -226,6 +370,46 @@ impl TryFrom<(String, ParametersExpression)> for Command {
                     options: opts,
                 }))
             }
+            "watch" => Ok(Command::Watch(WatchQuery {
+                pipeline: get_watch_pipeline(&params)?,
+            })),
+            "bulkwrite" => {
+                if params.params.len() != 1 {
+                    return Err(InterpreterError {
+                        message: "BulkWrite requires exactly 1 parameter".to_string(),
+                    });
+                }
+
+                let operations = try_from!(<ArrayExpression>(params.params[0].clone()))?
+                    .elements
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, operation)| {
+                        let operation = try_from!(<ObjectExpression>(operation))
+                            .and_then(|operation| to_interpter_error!(to_document(&operation)))
+                            .and_then(parse_extended_json)
+                            .and_then(WriteModel::try_from);
+                        operation.map_err(|err| InterpreterError {
+                            message: format!("BulkWrite operation {}: {}", index, err.message),
+                        })
+                    })
+                    .collect::<Result<Vec<WriteModel>, InterpreterError>>()?;
+
+                if operations.is_empty() {
+                    return Err(InterpreterError {
+                        message: "BulkWrite requires at least one operation".to_string(),
+                    });
+                }
+                ensure_write_allowed(&command)?;
+                if operations
+                    .iter()
+                    .any(|operation| !matches!(operation, WriteModel::InsertOne(_)))
+                {
+                    ensure_destructive_allowed(&command)?;
+                }
+
+                Ok(Command::BulkWrite(BulkWriteQuery { operations }))
+            }
             _ => Err(InterpreterError {
                 message: (format!("Command {} not implemented", command)),
             }),
@@ -233,45 +417,235 @@ impl TryFrom<(String, ParametersExpression)> for Command {
     }
 }
 
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct FindQuery {
     options: FindOptions,
     count: bool,
     filter: Option<Document>,
-    explain: bool,
+    /// Verbosity of the explain command, `None` runs the query itself
+    explain: Option<String>,
+    /// Follows the collection with a tailable cursor instead of fetching a page
+    tail: bool,
 }
 
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct GetIndexesQuery;
 
-#[derive(Default)]
+#[derive(Debug, Default)]
+pub struct DropQuery;
+
+#[derive(Debug, Default)]
+pub struct WatchQuery {
+    /// Stages filtering the change events, e.g. `{$match: {operationType: "insert"}}`
+    pipeline: Vec<Document>,
+}
+
+#[derive(Debug, Default)]
+pub struct StatsQuery {
+    /// Merged into the collStats command, e.g. `{scale: 1024}`
+    options: Option<Document>,
+}
+
+#[derive(Debug)]
+pub struct CreateIndexQuery {
+    index: IndexModel,
+}
+
+#[derive(Debug)]
+pub struct DropIndexQuery {
+    /// Either name of the index or its keys
+    index: Bson,
+}
+
+#[derive(Debug, Default)]
 pub struct AggregateQuery {
     pipelines: Vec<Document>,
     options: AggregateOptions,
     skip: Option<u64>,
     limit: Option<i64>,
-    explain: bool,
+    /// Verbosity of the explain command, `None` runs the query itself
+    explain: Option<String>,
 }
 
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct CountQuery {
     filter: Option<Document>,
     options: AggregateOptions,
 }
 
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct DistinctQuery {
     field: String,
     filter: Option<Document>,
     options: DistinctOptions,
 }
 
+#[derive(Debug)]
+pub struct BulkWriteQuery {
+    operations: Vec<WriteModel>,
+}
+
+/// Operators of the bulkWrite operation objects, e.g. `{insertOne: {document: {...}}}`
+const WRITE_OPERATORS: [&str; 6] = [
+    "insertOne",
+    "updateOne",
+    "updateMany",
+    "replaceOne",
+    "deleteOne",
+    "deleteMany",
+];
+
+#[derive(Debug)]
+pub enum WriteModel {
+    InsertOne(Document),
+    UpdateOne {
+        filter: Document,
+        update: UpdateModifications,
+        upsert: Option<bool>,
+    },
+    UpdateMany {
+        filter: Document,
+        update: UpdateModifications,
+        upsert: Option<bool>,
+    },
+    ReplaceOne {
+        filter: Document,
+        replacement: Document,
+        upsert: Option<bool>,
+    },
+    DeleteOne(Document),
+    DeleteMany(Document),
+}
+
+impl TryFrom<Document> for WriteModel {
+    type Error = InterpreterError;
+
+    fn try_from(operation: Document) -> Result<Self, Self::Error> {
+        let mut operators = operation.into_iter();
+        let (operator, body) = match (operators.next(), operators.next()) {
+            (Some((operator, Bson::Document(body))), None)
+                if WRITE_OPERATORS.contains(&operator.as_str()) =>
+            {
+                (operator, body)
+            }
+            _ => {
+                return Err(InterpreterError {
+                    message: format!(
+                        "Operation must be an object with exactly one of {} keys",
+                        WRITE_OPERATORS.join(", ")
+                    ),
+                })
+            }
+        };
+
+        let get_document = |field: &str| {
+            body.get_document(field)
+                .cloned()
+                .map_err(|_| InterpreterError {
+                    message: format!("{} requires '{}' object", operator, field),
+                })
+        };
+        let get_update = || match body.get("update") {
+            Some(Bson::Document(update)) => Ok(UpdateModifications::Document(update.clone())),
+            Some(Bson::Array(pipeline)) => Ok(UpdateModifications::Pipeline(
+                pipeline
+                    .iter()
+                    .filter_map(|stage| stage.as_document().cloned())
+                    .collect(),
+            )),
+            _ => Err(InterpreterError {
+                message: format!("{} requires 'update' object or pipeline", operator),
+            }),
+        };
+        let upsert = body.get_bool("upsert").ok();
+
+        Ok(match operator.as_str() {
+            "insertOne" => WriteModel::InsertOne(get_document("document")?),
+            "updateOne" => WriteModel::UpdateOne {
+                filter: get_document("filter")?,
+                update: get_update()?,
+                upsert,
+            },
+            "updateMany" => WriteModel::UpdateMany {
+                filter: get_document("filter")?,
+                update: get_update()?,
+                upsert,
+            },
+            "replaceOne" => WriteModel::ReplaceOne {
+                filter: get_document("filter")?,
+                replacement: get_document("replacement")?,
+                upsert,
+            },
+            "deleteOne" => WriteModel::DeleteOne(get_document("filter")?),
+            _ => WriteModel::DeleteMany(get_document("filter")?),
+        })
+    }
+}
+
+#[derive(Debug)]
 pub enum Command {
     Find(FindQuery),
     Count(CountQuery),
     Aggregate(AggregateQuery),
     Distinct(DistinctQuery),
     GetIndexes(GetIndexesQuery),
+    Drop(DropQuery),
+    Stats(StatsQuery),
+    CreateIndex(CreateIndexQuery),
+    DropIndex(DropIndexQuery),
+    BulkWrite(BulkWriteQuery),
+    Watch(WatchQuery),
+}
+
+impl Command {
+    /// Running the command again would repeat its writes
+    pub fn is_write(&self) -> bool {
+        match self {
+            Command::Aggregate(aggregate) => aggregate.is_write(),
+            Command::Drop(_)
+            | Command::CreateIndex(_)
+            | Command::DropIndex(_)
+            | Command::BulkWrite(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Keeps waiting for new documents, e.g. `tail()` or `watch()`, so it never returns a result
+    pub fn is_endless(&self) -> bool {
+        match self {
+            Command::Find(find) => find.tail,
+            Command::Watch(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Lines of the driver call, as `driver call` rows. Pagination of the page is not part of it
+    /// and options that are not set are left out
+    pub fn get_driver_call(&self, database: &str, collection: &str) -> DatabaseData {
+        let command = format!("{:#?}", self);
+        let lines = [
+            format!("database: {}", database),
+            format!("collection: {}", collection),
+        ]
+        .into_iter()
+        .chain(
+            command
+                .lines()
+                .filter(|line| !line.ends_with(": None,"))
+                .map(String::from),
+        );
+
+        DatabaseData(
+            lines
+                .map(|line| {
+                    Object(HashMap::from([(
+                        String::from("driver call"),
+                        DatabaseValue::String(line),
+                    )]))
+                })
+                .collect(),
+        )
+    }
 }
 
 // TODO: Update queries
@@ -283,6 +657,7 @@ impl QueryBuilder for Command {
             Command::Find(find) => find.add_sub_query(query),
             Command::Count(count) => count.add_sub_query(query),
             Command::Aggregate(aggregate) => aggregate.add_sub_query(query),
+            Command::Watch(watch) => watch.add_sub_query(query),
             _ => self.add_sub_query(query),
         }
     }
@@ -303,6 +678,18 @@ impl QueryBuilder for Command {
             Command::GetIndexes(get_indexes) => {
                 get_indexes.build(collection, pagination, database).await
             }
+            Command::Drop(drop) => drop.build(collection, pagination, database).await,
+            Command::Stats(stats) => stats.build(collection, pagination, database).await,
+            Command::CreateIndex(create_index) => {
+                create_index.build(collection, pagination, database).await
+            }
+            Command::DropIndex(drop_index) => {
+                drop_index.build(collection, pagination, database).await
+            }
+            Command::BulkWrite(bulk_write) => {
+                bulk_write.build(collection, pagination, database).await
+            }
+            Command::Watch(watch) => watch.build(collection, pagination, database).await,
         }
     }
 }
@@ -310,7 +697,6 @@ impl QueryBuilder for Command {
 #[async_trait]
 impl QueryBuilder for FindQuery {
     fn add_sub_query(&mut self, query: SubCommand) -> Result<(), InterpreterError> {
-        self.options.batch_size = Some(50);
         match query {
             SubCommand::Count => {
                 self.count = true;
@@ -321,8 +707,8 @@ impl QueryBuilder for FindQuery {
             SubCommand::AllowDiskUse => {
                 self.options.allow_disk_use = Some(true);
             }
-            SubCommand::Explain => {
-                self.explain = true;
+            SubCommand::Explain(verbosity) => {
+                self.explain = Some(verbosity);
             }
             SubCommand::Hint(hint) => {
                 self.options.hint = hint;
@@ -333,6 +719,24 @@ impl QueryBuilder for FindQuery {
             SubCommand::Limit(amount) => {
                 self.options.limit = amount;
             }
+            SubCommand::MaxTimeMS(max_time) => {
+                self.options.max_time = Some(max_time);
+            }
+            SubCommand::ReadConcern(read_concern) => {
+                self.options.read_concern = Some(read_concern);
+            }
+            SubCommand::ReadPreference(selection_criteria) => {
+                self.options.selection_criteria = Some(selection_criteria);
+            }
+            SubCommand::Project(projection) => {
+                self.options.projection = Some(projection);
+            }
+            SubCommand::Tail => {
+                self.tail = true;
+            }
+            SubCommand::Comment(comment) => {
+                self.options.comment = Some(comment);
+            }
         }
 
         Ok(())
@@ -344,7 +748,32 @@ impl QueryBuilder for FindQuery {
         pagination: PaginationInfo,
         database: Database,
     ) -> Result<DatabaseResponse, mongodb::error::Error> {
-        Ok(if self.explain {
+        Ok(if self.count {
+            let mut pipelines = Vec::new();
+            if self.filter.is_some() {
+                pipelines.push(doc! { "$match": self.filter.unwrap()});
+            };
+            pipelines.push(doc! {"$count": "count"});
+
+            if let Some(verbosity) = self.explain {
+                return Ok(DatabaseResponse::Bson(vec![mongodb::bson::Bson::Document(
+                    database
+                        .run_command(
+                            get_aggregate_explain_command(collection.name(), pipelines, verbosity),
+                            None,
+                        )
+                        .await?,
+                )]));
+            }
+
+            let mut aggregate_options = AggregateOptions::default();
+            aggregate_options.allow_disk_use = self.options.allow_disk_use;
+            aggregate_options.max_time = Some(self.options.max_time.unwrap_or_else(get_max_time));
+            aggregate_options.read_concern = self.options.read_concern;
+            aggregate_options.selection_criteria = self.options.selection_criteria;
+
+            DatabaseResponse::Cursor(collection.aggregate(pipelines, aggregate_options).await?)
+        } else if let Some(verbosity) = self.explain {
             let mut doc = Document::new();
 
             let mut map = Map::new();
@@ -373,44 +802,109 @@ impl QueryBuilder for FindQuery {
             }
 
             doc.insert("explain", Bson::try_from(map).unwrap());
+            doc.insert("verbosity", verbosity);
 
             DatabaseResponse::Bson(vec![mongodb::bson::Bson::Document(
                 database.run_command(doc, None).await?,
             )])
-        } else if self.count {
-            // TODO: Explain does not work with count currently
-            let mut pipelines = Vec::new();
-            if self.filter.is_some() {
-                pipelines.push(doc! { "$match": self.filter.unwrap()});
-            };
-            pipelines.push(doc! {"$count": "count"});
-
-            let mut aggregate_options = AggregateOptions::default();
-            aggregate_options.allow_disk_use = self.options.allow_disk_use;
+        } else if self.tail {
+            // Server keeps the cursor open for documents inserted later, it fails for collections
+            // that are not capped
+            self.options.cursor_type = Some(CursorType::TailableAwait);
+            self.options.batch_size = Some(get_batch_size(pagination));
 
-            DatabaseResponse::Cursor(collection.aggregate(pipelines, aggregate_options).await?)
+            DatabaseResponse::TailableCursor(collection.find(self.filter, self.options).await?)
         } else {
             self.options.skip = Some(pagination.start);
             self.options.limit = Some(self.options.limit.unwrap_or(pagination.limit as i64));
+            self.options.max_time.get_or_insert_with(get_max_time);
+            self.options.batch_size = Some(get_batch_size(pagination));
 
             DatabaseResponse::Cursor(collection.find(self.filter, self.options).await?)
         })
     }
 }
 
+impl FindQuery {
+    /// Counts all documents matching the filter regardless of skip and limit, queries that are
+    /// explained or counted already have nothing to count
+    pub async fn count_documents(
+        self,
+        collection: Collection<Document>,
+    ) -> Result<Option<u64>, mongodb::error::Error> {
+        if self.count || self.explain.is_some() {
+            return Ok(None);
+        }
+
+        let mut count_options = CountOptions::default();
+        count_options.hint = self.options.hint;
+        count_options.max_time = Some(self.options.max_time.unwrap_or_else(get_max_time));
+        count_options.read_concern = self.options.read_concern;
+        count_options.selection_criteria = self.options.selection_criteria;
+
+        collection
+            .count_documents(self.filter, count_options)
+            .await
+            .map(Some)
+    }
+}
+
 #[async_trait]
 impl QueryBuilder for DistinctQuery {
+    async fn build(
+        mut self,
+        collection: Collection<Document>,
+        _: PaginationInfo,
+        _: Database,
+    ) -> Result<DatabaseResponse, mongodb::error::Error> {
+        self.options.max_time.get_or_insert_with(get_max_time);
+
+        let values = collection
+            .distinct(&self.field, self.filter, self.options)
+            .await?;
+
+        Ok(DatabaseResponse::Distinct(self.field, values))
+    }
+}
+
+/// Pipeline of the change stream is optional, all events are watched without it
+fn get_watch_pipeline(params: &ParametersExpression) -> Result<Vec<Document>, InterpreterError> {
+    if params.params.len() > 1 {
+        return Err(InterpreterError {
+            message: "Watch command only accepts 1 parameter".to_string(),
+        });
+    }
+
+    match params.params.first() {
+        Some(param) => try_from!(<ArrayExpression>(param.clone()))?
+            .elements
+            .into_iter()
+            .map(|stage| {
+                let object = try_from!(<ObjectExpression>(stage))?;
+                to_interpter_error!(to_document(&object)).and_then(parse_extended_json)
+            })
+            .collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[async_trait]
+impl QueryBuilder for WatchQuery {
     async fn build(
         self,
         collection: Collection<Document>,
         _: PaginationInfo,
         _: Database,
     ) -> Result<DatabaseResponse, mongodb::error::Error> {
-        Ok(DatabaseResponse::Bson(
-            collection
-                .distinct(self.field, self.filter, self.options)
-                .await?,
-        ))
+        // Updates show the whole updated document, not only the changed fields
+        let options = ChangeStreamOptions::builder()
+            .full_document(Some(FullDocumentType::UpdateLookup))
+            .build();
+        let change_stream = collection.watch(self.pipeline, options).await?;
+
+        Ok(DatabaseResponse::ChangeStream(Box::new(
+            change_stream.with_type::<Document>(),
+        )))
     }
 }
 
@@ -428,6 +922,169 @@ impl QueryBuilder for GetIndexesQuery {
     }
 }
 
+#[async_trait]
+impl QueryBuilder for DropQuery {
+    async fn build(
+        self,
+        collection: Collection<Document>,
+        _: PaginationInfo,
+        _: Database,
+    ) -> Result<DatabaseResponse, mongodb::error::Error> {
+        collection.drop(None).await?;
+
+        Ok(DatabaseResponse::Bson(vec![Bson::Document(
+            doc! {"dropped": collection.name()},
+        )]))
+    }
+}
+
+/// Fields of the collStats output shown to the user, the rest is mostly storage engine internals
+const STATS_FIELDS: [&str; 7] = [
+    "ns",
+    "count",
+    "size",
+    "avgObjSize",
+    "storageSize",
+    "totalIndexSize",
+    "indexSizes",
+];
+
+#[async_trait]
+impl QueryBuilder for StatsQuery {
+    async fn build(
+        self,
+        collection: Collection<Document>,
+        _: PaginationInfo,
+        database: Database,
+    ) -> Result<DatabaseResponse, mongodb::error::Error> {
+        let mut command = doc! {"collStats": collection.name()};
+        if let Some(options) = self.options {
+            command.extend(options);
+        }
+
+        let stats = database.run_command(command, None).await?;
+
+        Ok(DatabaseResponse::Bson(vec![Bson::Document(
+            stats
+                .into_iter()
+                .filter(|(key, _)| STATS_FIELDS.contains(&key.as_str()))
+                .collect(),
+        )]))
+    }
+}
+
+#[async_trait]
+impl QueryBuilder for CreateIndexQuery {
+    async fn build(
+        mut self,
+        collection: Collection<Document>,
+        _: PaginationInfo,
+        _: Database,
+    ) -> Result<DatabaseResponse, mongodb::error::Error> {
+        let result = collection.create_index(self.index.clone(), None).await?;
+
+        let mut options = self.index.options.unwrap_or_default();
+        options.name = Some(result.index_name);
+        self.index.options = Some(options);
+
+        Ok(DatabaseResponse::Indexes(vec![self.index]))
+    }
+}
+
+#[async_trait]
+impl QueryBuilder for DropIndexQuery {
+    async fn build(
+        self,
+        collection: Collection<Document>,
+        _: PaginationInfo,
+        database: Database,
+    ) -> Result<DatabaseResponse, mongodb::error::Error> {
+        database
+            .run_command(
+                doc! {"dropIndexes": collection.name(), "index": self.index.clone()},
+                None,
+            )
+            .await?;
+
+        Ok(DatabaseResponse::Bson(vec![Bson::Document(
+            doc! {"dropped": self.index},
+        )]))
+    }
+}
+
+#[async_trait]
+impl QueryBuilder for BulkWriteQuery {
+    /// The driver has no bulk write API, so the operations run one by one and the ones before a
+    /// failing operation stay applied
+    async fn build(
+        self,
+        collection: Collection<Document>,
+        _: PaginationInfo,
+        _: Database,
+    ) -> Result<DatabaseResponse, mongodb::error::Error> {
+        let (mut inserted, mut matched, mut modified, mut deleted, mut upserted) = (0, 0, 0, 0, 0);
+
+        for operation in self.operations {
+            let update_result = match operation {
+                WriteModel::InsertOne(document) => {
+                    collection.insert_one(document, None).await?;
+                    inserted += 1;
+                    None
+                }
+                WriteModel::UpdateOne {
+                    filter,
+                    update,
+                    upsert,
+                } => {
+                    let mut options = UpdateOptions::default();
+                    options.upsert = upsert;
+                    Some(collection.update_one(filter, update, options).await?)
+                }
+                WriteModel::UpdateMany {
+                    filter,
+                    update,
+                    upsert,
+                } => {
+                    let mut options = UpdateOptions::default();
+                    options.upsert = upsert;
+                    Some(collection.update_many(filter, update, options).await?)
+                }
+                WriteModel::ReplaceOne {
+                    filter,
+                    replacement,
+                    upsert,
+                } => {
+                    let mut options = ReplaceOptions::default();
+                    options.upsert = upsert;
+                    Some(collection.replace_one(filter, replacement, options).await?)
+                }
+                WriteModel::DeleteOne(filter) => {
+                    deleted += collection.delete_one(filter, None).await?.deleted_count;
+                    None
+                }
+                WriteModel::DeleteMany(filter) => {
+                    deleted += collection.delete_many(filter, None).await?.deleted_count;
+                    None
+                }
+            };
+
+            if let Some(result) = update_result {
+                matched += result.matched_count;
+                modified += result.modified_count;
+                upserted += u64::from(result.upserted_id.is_some());
+            }
+        }
+
+        Ok(DatabaseResponse::Bson(vec![Bson::Document(doc! {
+            "insertedCount": inserted as i64,
+            "matchedCount": matched as i64,
+            "modifiedCount": modified as i64,
+            "deletedCount": deleted as i64,
+            "upsertedCount": upserted as i64,
+        })]))
+    }
+}
+
 #[async_trait]
 impl QueryBuilder for CountQuery {
     fn add_sub_query(&mut self, query: SubCommand) -> Result<(), InterpreterError> {
@@ -436,8 +1093,12 @@ impl QueryBuilder for CountQuery {
                 self.options.allow_disk_use = Some(true);
                 Ok(())
             }
+            SubCommand::MaxTimeMS(max_time) => {
+                self.options.max_time = Some(max_time);
+                Ok(())
+            }
             _ => Err(InterpreterError {
-                message: "Count only supports AllowDiskUse".to_string(),
+                message: "Count only supports AllowDiskUse and MaxTimeMS".to_string(),
             }),
         }
     }
@@ -455,6 +1116,7 @@ impl QueryBuilder for CountQuery {
 
         let mut aggregate_options = AggregateOptions::default();
         aggregate_options.allow_disk_use = self.options.allow_disk_use;
+        aggregate_options.max_time = Some(self.options.max_time.unwrap_or_else(get_max_time));
 
         Ok(DatabaseResponse::Cursor(
             collection.aggregate(pipelines, aggregate_options).await?,
@@ -462,23 +1124,79 @@ impl QueryBuilder for CountQuery {
     }
 }
 
-#[async_trait]
-impl QueryBuilder for AggregateQuery {
-    fn add_sub_query(&mut self, query: SubCommand) -> Result<(), InterpreterError> {
+impl AggregateQuery {
+    fn is_write(&self) -> bool {
+        self.pipelines
+            .iter()
+            .any(|stage| get_write_stage(stage).is_some())
+    }
+
+    /// Appends `$skip` and `$limit` stages for the current page, except for pipelines that
+    /// - write into a collection, `$out`/`$merge` has to be the last stage and the pagination
+    ///   would limit what gets written
+    /// - end with a stage producing a single document, e.g. `$count`, there is nothing to page
+    fn paginate(&mut self, pagination: PaginationInfo) {
+        if !is_paginated_pipeline(&self.pipelines) {
+            return;
+        }
+
+        self.pipelines
+            .push(doc! {"$skip": (pagination.start + self.skip.unwrap_or(0)) as u32});
+        self.pipelines
+            .push(doc! {"$limit": self.limit.unwrap_or(pagination.limit as i64) });
+    }
+}
+
+/// Stages writing results of the aggregation into a collection
+const WRITE_STAGES: [&str; 2] = ["$out", "$merge"];
+
+/// Pipeline neither writes into a collection nor ends with a stage producing a single document,
+/// the page is appended to it
+pub fn is_paginated_pipeline(pipeline: &[Document]) -> bool {
+    !pipeline
+        .iter()
+        .any(|stage| get_write_stage(stage).is_some())
+        && !pipeline.last().is_some_and(is_single_document_stage)
+}
+
+/// `$count`, or `$group` accumulating the whole input into one group
+fn is_single_document_stage(stage: &Document) -> bool {
+    stage.contains_key("$count")
+        || stage
+            .get_document("$group")
+            .is_ok_and(|group| matches!(group.get("_id"), Some(Bson::Null)))
+}
+
+fn get_write_stage(stage: &Document) -> Option<&'static str> {
+    WRITE_STAGES
+        .into_iter()
+        .find(|write_stage| stage.contains_key(write_stage))
+}
+
+#[async_trait]
+impl QueryBuilder for AggregateQuery {
+    fn add_sub_query(&mut self, query: SubCommand) -> Result<(), InterpreterError> {
         match query {
             SubCommand::Count => todo!(),
             SubCommand::AllowDiskUse => {
                 self.options.allow_disk_use = Some(true);
                 Ok(())
             }
-            SubCommand::Explain => {
-                self.explain = true;
+            SubCommand::Explain(verbosity) => {
+                self.explain = Some(verbosity);
                 Ok(())
             }
             SubCommand::Hint(hint) => {
                 self.options.hint = hint;
                 Ok(())
             }
+            SubCommand::Skip(_) | SubCommand::Limit(_) if self.is_write() => {
+                Err(InterpreterError {
+                    message: "Aggregate writing into a collection does not support skip or limit, \
+                          use $skip or $limit stage instead"
+                        .to_string(),
+                })
+            }
             SubCommand::Skip(amount) => {
                 self.skip = amount;
                 Ok(())
@@ -487,6 +1205,22 @@ impl QueryBuilder for AggregateQuery {
                 self.limit = amount;
                 Ok(())
             }
+            SubCommand::MaxTimeMS(max_time) => {
+                self.options.max_time = Some(max_time);
+                Ok(())
+            }
+            SubCommand::ReadConcern(read_concern) => {
+                self.options.read_concern = Some(read_concern);
+                Ok(())
+            }
+            SubCommand::ReadPreference(selection_criteria) => {
+                self.options.selection_criteria = Some(selection_criteria);
+                Ok(())
+            }
+            SubCommand::Comment(comment) => {
+                self.options.comment = Some(comment);
+                Ok(())
+            }
             _ => Err(InterpreterError {
                 message: format!("Aggregate does not support {:?}", query),
             }),
@@ -501,39 +1235,22 @@ impl QueryBuilder for AggregateQuery {
     ) -> Result<DatabaseResponse, mongodb::error::Error> {
         let mut aggregate_options = AggregateOptions::default();
         aggregate_options.allow_disk_use = self.options.allow_disk_use;
+        aggregate_options.max_time = Some(self.options.max_time.unwrap_or_else(get_max_time));
+        aggregate_options.hint = self.options.hint.take();
+        aggregate_options.batch_size = Some(get_batch_size(pagination));
+        aggregate_options.read_concern = self.options.read_concern.take();
+        aggregate_options.selection_criteria = self.options.selection_criteria.take();
 
-        self.pipelines
-            .push(doc! {"$skip": (pagination.start + self.skip.unwrap_or(0)) as u32});
-        self.pipelines
-            .push(doc! {"$limit": self.limit.unwrap_or(pagination.limit as i64) });
-
-        if self.explain {
-            let mut doc = Document::new();
-
-            let mut map = Map::new();
-
-            map.insert(String::from("aggregate"), collection.name().into());
-            map.insert(
-                String::from("pipeline"),
-                self.pipelines
-                    .into_iter()
-                    .map(|pipeline| {
-                        mongodb::bson::from_bson::<serde_json::Value>(
-                            mongodb::bson::Bson::Document(pipeline),
-                        )
-                        .unwrap()
-                    })
-                    .collect(),
-            );
-            // Required for MongoDB aggregation queries, but it can be left empty for explain purposes
-            map.insert(
-                String::from("cursor"),
-                mongodb::bson::from_document(Document::new()).unwrap(),
-            );
-            doc.insert("explain", Bson::try_from(map).unwrap());
+        self.paginate(pagination);
 
+        if let Some(verbosity) = self.explain {
             return Ok(DatabaseResponse::Bson(vec![mongodb::bson::Bson::Document(
-                database.run_command(doc, None).await?,
+                database
+                    .run_command(
+                        get_aggregate_explain_command(collection.name(), self.pipelines, verbosity),
+                        None,
+                    )
+                    .await?,
             )]));
         }
 
@@ -545,11 +1262,414 @@ impl QueryBuilder for AggregateQuery {
     }
 }
 
+/// Variables starting with uppercase letter are reserved for these, so a typo would only fail on
+/// the server
+const SYSTEM_VARIABLES: [&str; 10] = [
+    "NOW",
+    "CLUSTER_TIME",
+    "ROOT",
+    "CURRENT",
+    "REMOVE",
+    "DESCEND",
+    "PRUNE",
+    "KEEP",
+    "USER_ROLES",
+    "SEARCH_META",
+];
+
+/// Checks operators whose malformed values would only be rejected by the server, `$expr` has
+/// to hold an aggregation expression and `$jsonSchema` an object. `path` is the property path of
+/// the filter, used in the error message
+fn validate_filter(filter: &Document, path: &str) -> Result<(), InterpreterError> {
+    filter.iter().try_for_each(|(key, value)| {
+        let path = join_path(path, key);
+        match key.as_str() {
+            "$expr" => validate_expression(value, &path, true),
+            "$jsonSchema" if !matches!(value, Bson::Document(_)) => Err(InterpreterError {
+                message: format!(
+                    "Invalid $jsonSchema at '{}', expected an object, found {}",
+                    path, value
+                ),
+            }),
+            "$jsonSchema" => Ok(()),
+            _ => validate_filter_value(value, &path),
+        }
+    })
+}
+
+fn validate_filter_value(value: &Bson, path: &str) -> Result<(), InterpreterError> {
+    match value {
+        Bson::Document(doc) => validate_filter(doc, path),
+        Bson::Array(arr) => arr.iter().enumerate().try_for_each(|(index, value)| {
+            validate_filter_value(value, &join_path(path, &index.to_string()))
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Expression objects name exactly one operator, e.g. `{$gt: ['$a', '$b']}`, the value of `$expr`
+/// itself may also be a field path or a boolean
+fn validate_expression(value: &Bson, path: &str, is_root: bool) -> Result<(), InterpreterError> {
+    match value {
+        Bson::Document(doc) => {
+            if doc.len() > 1 && doc.keys().any(|key| key.starts_with('$')) {
+                return Err(InterpreterError {
+                    message: format!(
+                        "Invalid expression at '{}', an expression object must contain exactly one operator, found {}",
+                        path,
+                        doc.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ),
+                });
+            }
+            doc.iter().try_for_each(|(key, value)| {
+                validate_expression(value, &join_path(path, key), false)
+            })
+        }
+        Bson::Array(arr) => arr.iter().enumerate().try_for_each(|(index, value)| {
+            validate_expression(value, &join_path(path, &index.to_string()), false)
+        }),
+        Bson::String(str) if is_root && !str.starts_with('$') => Err(InterpreterError {
+            message: format!(
+                "Invalid $expr at '{}', expected an aggregation expression such as {{$gt: ['$a', '$b']}} or a field path, found '{}'",
+                path, str
+            ),
+        }),
+        Bson::String(_) | Bson::Boolean(_) => Ok(()),
+        value if is_root => Err(InterpreterError {
+            message: format!(
+                "Invalid $expr at '{}', expected an aggregation expression such as {{$gt: ['$a', '$b']}}, found {}",
+                path, value
+            ),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Joins either match `localField` with `foreignField` of the `from` collection, or run the
+/// `pipeline` with the variables of `let`, both forms may be combined. Stages of the pipeline are
+/// validated as well, `path` is used in the error message
+fn validate_lookup(lookup: &Bson, path: &str) -> Result<(), InterpreterError> {
+    let Bson::Document(lookup) = lookup else {
+        return Err(InterpreterError {
+            message: format!(
+                "Invalid {} stage, expected an object, found {}",
+                path, lookup
+            ),
+        });
+    };
+    let missing = |key: &str| InterpreterError {
+        message: format!("Invalid {} stage, missing '{}'", path, key),
+    };
+
+    if !lookup.contains_key("as") {
+        return Err(missing("as"));
+    }
+    match (
+        lookup.contains_key("localField"),
+        lookup.contains_key("foreignField"),
+    ) {
+        (true, false) => return Err(missing("foreignField")),
+        (false, true) => return Err(missing("localField")),
+        _ => {}
+    }
+
+    // Only a pipeline may go without `from`, it starts with `$documents` instead of reading a
+    // collection then
+    let Some(pipeline) = lookup.get("pipeline") else {
+        if lookup.contains_key("let") {
+            return Err(missing("pipeline"));
+        }
+        if !lookup.contains_key("localField") {
+            return Err(InterpreterError {
+                message: format!(
+                    "Invalid {} stage, either 'localField' with 'foreignField' or 'pipeline' is \
+                     required",
+                    path
+                ),
+            });
+        }
+        if !lookup.contains_key("from") {
+            return Err(missing("from"));
+        }
+        return Ok(());
+    };
+    let Bson::Array(stages) = pipeline else {
+        return Err(InterpreterError {
+            message: format!(
+                "Invalid {}.pipeline, expected an array of stages, found {}",
+                path, pipeline
+            ),
+        });
+    };
+    stages
+        .iter()
+        .enumerate()
+        .try_for_each(|(idx, stage)| match stage {
+            Bson::Document(stage) => match stage.get("$lookup") {
+                Some(lookup) => validate_lookup(
+                    lookup,
+                    &join_path(path, &format!("pipeline.{}.$lookup", idx)),
+                ),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        })
+}
+
+/// Replaces Extended JSON values, e.g. of documents copied from Compass, with the types they
+/// stand for. `{$oid: "..."}` is an ObjectId, `{$date: ...}` a date given as an ISO-8601 string,
+/// milliseconds since the epoch or `{$numberLong: "..."}`
+fn parse_extended_json(doc: Document) -> Result<Document, InterpreterError> {
+    doc.into_iter()
+        .map(|(key, value)| Ok((key, parse_extended_json_value(value)?)))
+        .collect()
+}
+
+fn parse_extended_json_value(value: Bson) -> Result<Bson, InterpreterError> {
+    match value {
+        Bson::Document(doc) if doc.len() == 1 && doc.contains_key("$oid") => match &doc["$oid"] {
+            Bson::String(hex) => {
+                ObjectId::parse_str(hex)
+                    .map(Bson::ObjectId)
+                    .map_err(|_| InterpreterError {
+                        message: format!("Invalid $oid '{}', expected 24 hex characters", hex),
+                    })
+            }
+            value => Err(InterpreterError {
+                message: format!("Invalid $oid {}, expected a string", value),
+            }),
+        },
+        Bson::Document(doc) if doc.len() == 1 && doc.contains_key("$date") => {
+            let millis = match &doc["$date"] {
+                Bson::String(date) => {
+                    return mongodb::bson::DateTime::parse_rfc3339_str(date)
+                        .map(Bson::DateTime)
+                        .map_err(|_| InterpreterError {
+                            message: format!(
+                                "Invalid $date '{}', expected an ISO-8601 date, e.g. 2024-01-31T12:00:00Z",
+                                date
+                            ),
+                        })
+                }
+                Bson::Int32(millis) => Some(*millis as i64),
+                Bson::Int64(millis) => Some(*millis),
+                Bson::Document(number) => number
+                    .get_str("$numberLong")
+                    .ok()
+                    .and_then(|millis| millis.parse().ok()),
+                _ => None,
+            };
+            match millis {
+                Some(millis) => Ok(Bson::DateTime(mongodb::bson::DateTime::from_millis(millis))),
+                None => Err(InterpreterError {
+                    message: format!(
+                        "Invalid $date {}, expected an ISO-8601 date or milliseconds since the epoch",
+                        doc["$date"]
+                    ),
+                }),
+            }
+        }
+        Bson::Document(doc) => parse_extended_json(doc).map(Bson::Document),
+        Bson::Array(arr) => arr
+            .into_iter()
+            .map(parse_extended_json_value)
+            .collect::<Result<_, _>>()
+            .map(Bson::Array),
+        value => Ok(value),
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn validate_system_variables(value: &Bson) -> Result<(), InterpreterError> {
+    match value {
+        Bson::String(str) => {
+            let Some(variable) = str.strip_prefix("$$") else {
+                return Ok(());
+            };
+            // Path to a field of the variable, e.g. $$ROOT.name
+            let name = variable.split('.').next().unwrap_or_default();
+            if name.starts_with(|c: char| c.is_ascii_uppercase())
+                && !SYSTEM_VARIABLES.contains(&name)
+            {
+                return Err(InterpreterError {
+                    message: format!(
+                        "Unknown system variable $${}, expected one of {}",
+                        name,
+                        SYSTEM_VARIABLES.join(", ")
+                    ),
+                });
+            }
+            Ok(())
+        }
+        Bson::Document(doc) => doc.values().try_for_each(validate_system_variables),
+        Bson::Array(arr) => arr.iter().try_for_each(validate_system_variables),
+        _ => Ok(()),
+    }
+}
+
+/// Error of the driver as its message with the MongoDB error code, the driver's own description
+/// (`Kind: Command failed: ..., labels: {}`) only goes to the debug log
+pub fn to_driver_error(err: mongodb::error::Error) -> InterpreterError {
+    DEBUG_FILE.write_log(&err);
+
+    let (code, code_name, message) = match *err.kind {
+        ErrorKind::Command(err) => (err.code, Some(err.code_name), err.message),
+        ErrorKind::Write(WriteFailure::WriteError(err)) => (err.code, err.code_name, err.message),
+        ErrorKind::Write(WriteFailure::WriteConcernError(err)) => {
+            (err.code, Some(err.code_name), err.message)
+        }
+        ErrorKind::BulkWrite(BulkWriteFailure {
+            write_errors: Some(mut errors),
+            ..
+        }) if !errors.is_empty() => {
+            let err = errors.remove(0);
+            (err.code, err.code_name, err.message)
+        }
+        ErrorKind::BulkWrite(BulkWriteFailure {
+            write_concern_error: Some(err),
+            ..
+        }) => (err.code, Some(err.code_name), err.message),
+        kind => {
+            return InterpreterError {
+                message: kind.to_string(),
+            }
+        }
+    };
+
+    InterpreterError {
+        message: match code_name.filter(|code_name| !code_name.is_empty()) {
+            Some(code_name) => format!("{} (error code {} {})", message, code, code_name),
+            None => format!("{} (error code {})", message, code),
+        },
+    }
+}
+
+/// Codes of the server errors caused by the state of the deployment rather than the query, e.g.
+/// `PrimarySteppedDown` while a new primary is elected
+const TRANSIENT_ERROR_CODES: [i32; 12] = [
+    6, 7, 89, 91, 189, 262, 9001, 10107, 11600, 11602, 13435, 13436,
+];
+
+/// Network errors and errors of a deployment that is temporarily unavailable, running the same
+/// request again may succeed
+pub fn is_transient_error(err: &mongodb::error::Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Io(_)
+        | ErrorKind::ConnectionPoolCleared { .. }
+        | ErrorKind::ServerSelection { .. } => true,
+        ErrorKind::Command(err) => TRANSIENT_ERROR_CODES.contains(&err.code),
+        _ => false,
+    }
+}
+
+/// Same as [`to_driver_error`], marked as [`TransientError`] when retrying may succeed
+fn to_connector_error(err: mongodb::error::Error) -> anyhow::Error {
+    let is_transient = is_transient_error(&err);
+    let message = to_driver_error(err).message;
+    if is_transient {
+        TransientError(message).into()
+    } else {
+        anyhow!(message)
+    }
+}
+
+/// Most documents a cursor fetches from the server at once
+const MAX_BATCH_SIZE: u32 = 1000;
+
+/// Batches of the cursor match the page, so a page is usually fetched in a single round trip
+fn get_batch_size(pagination: PaginationInfo) -> u32 {
+    pagination.limit.min(MAX_BATCH_SIZE)
+}
+
+/// Default server side limit for queries that don't set `.maxTimeMS()` themselves
+fn get_max_time() -> Duration {
+    Duration::from_secs(CLI_ARGS.query_timeout)
+}
+
+/// Replaces the password placeholder of the uri, so the password itself is never part of the uri
+fn set_password(client_opts: &mut ClientOptions, password: Option<String>) -> Result<()> {
+    let Some(credential) = &mut client_opts.credential else {
+        return Ok(());
+    };
+    if credential.password.as_deref() != Some(PASSWORD_PLACEHOLDER) {
+        return Ok(());
+    }
+
+    match password {
+        Some(password) => {
+            credential.password = Some(password);
+            Ok(())
+        }
+        None => Err(anyhow!(
+            "Password can only be typed in for the uri given on startup, use a profile or include it in the uri"
+        )),
+    }
+}
+
+/// Timeouts given in the uri, e.g. `serverSelectionTimeoutMS`, take precedence
+fn set_timeouts(client_opts: &mut ClientOptions) {
+    client_opts
+        .server_selection_timeout
+        .get_or_insert(Duration::from_secs(CLI_ARGS.server_selection_timeout));
+    client_opts
+        .connect_timeout
+        .get_or_insert(Duration::from_secs(CLI_ARGS.connect_timeout));
+}
+
+fn get_aggregate_explain_command(
+    collection_name: &str,
+    pipelines: Vec<Document>,
+    verbosity: String,
+) -> Document {
+    let mut doc = Document::new();
+
+    let mut map = Map::new();
+
+    map.insert(String::from("aggregate"), collection_name.into());
+    map.insert(
+        String::from("pipeline"),
+        pipelines
+            .into_iter()
+            .map(|pipeline| {
+                mongodb::bson::from_bson::<serde_json::Value>(mongodb::bson::Bson::Document(
+                    pipeline,
+                ))
+                .unwrap()
+            })
+            .collect(),
+    );
+    // Required for MongoDB aggregation queries, but it can be left empty for explain purposes
+    map.insert(
+        String::from("cursor"),
+        mongodb::bson::from_document(Document::new()).unwrap(),
+    );
+    doc.insert("explain", Bson::try_from(map).unwrap());
+    doc.insert("verbosity", verbosity);
+
+    doc
+}
+
 pub enum DatabaseResponse {
     Cursor(Cursor<Document>),
+    /// Waits for new documents, it's followed until the query is cancelled. The connector stays
+    /// locked meanwhile, so other queries, counts and describe wait for it
+    TailableCursor(Cursor<Document>),
+    /// Change events of the collection, followed until the query is cancelled. Like the tailable
+    /// cursor it keeps the connector locked
+    ChangeStream(Box<ChangeStream<Document>>),
     CursorCollectionSpec(Cursor<CollectionSpecification>),
     CursorIndexes(Cursor<IndexModel>),
+    Indexes(Vec<IndexModel>),
     Bson(Vec<Bson>),
+    /// Distinct values of the field, elements of array fields are already separate values
+    Distinct(String, Vec<Bson>),
 }
 
 #[async_trait]
@@ -567,18 +1687,99 @@ pub trait QueryBuilder {
     ) -> Result<DatabaseResponse, mongodb::error::Error>;
 }
 
+pub fn ensure_write_allowed(command: &str) -> Result<(), InterpreterError> {
+    if !CLI_ARGS.read_only {
+        return Ok(());
+    }
+
+    Err(InterpreterError {
+        message: format!(
+            "Command {} writes to the database, which is not allowed in read-only mode",
+            command
+        ),
+    })
+}
+
+/// Printed result of `--query` waits for the query to finish
+pub fn ensure_finite(command: &Command) -> Result<(), InterpreterError> {
+    if CLI_ARGS.query.is_none() || !command.is_endless() {
+        return Ok(());
+    }
+
+    Err(InterpreterError {
+        message: "Queries with tail() or watch() never finish, they can't be run with --query"
+            .to_string(),
+    })
+}
+
+pub fn ensure_destructive_allowed(command: &str) -> Result<(), InterpreterError> {
+    ensure_write_allowed(command)?;
+    if CLI_ARGS.allow_destructive {
+        return Ok(());
+    }
+
+    Err(InterpreterError {
+        message: format!(
+            "Command {} is destructive, start rdbcli with --allow-destructive to enable it",
+            command
+        ),
+    })
+}
+
+/// Allowed explain verbosities, the first one is used by default
+const EXPLAIN_VERBOSITIES: [&str; 3] = ["queryPlanner", "executionStats", "allPlansExecution"];
+
 // TODO: Limit, Skip
 #[derive(Debug)]
 pub enum SubCommand {
     Count,
     Sort(Option<Document>),
     AllowDiskUse,
-    Explain,
+    Explain(String),
     Hint(Option<mongodb::options::Hint>),
     Skip(Option<u64>),
     Limit(Option<i64>),
+    MaxTimeMS(Duration),
+    ReadConcern(ReadConcern),
+    ReadPreference(SelectionCriteria),
+    /// Replaces projection given as the second parameter of find
+    Project(Document),
+    /// Streams documents inserted into a capped collection, like `tail -f`
+    Tail,
+    /// Tags the query, so it can be found in `currentOp`, the profiler and the logs
+    Comment(String),
 }
 
+/// Parses the only parameter of readConcern/readPreference, either an object or a string
+/// shorthand for its `key`, e.g. `.readConcern("majority")` is `.readConcern({level: "majority"})`
+fn get_options_parameter<T: serde::de::DeserializeOwned>(
+    command: &str,
+    params: &ParametersExpression,
+    key: &str,
+) -> Result<T, InterpreterError> {
+    if params.params.len() != 1 {
+        return Err(InterpreterError {
+            message: format!("{} command requires exactly 1 parameter", command),
+        });
+    }
+
+    let options = match params.get_nth_of_type::<Identifier>(0)? {
+        Identifier::Literal(Literal::String(value)) => doc! {key: value},
+        Identifier::Object(options) => to_interpter_error!(to_document(&options))?,
+        _ => {
+            return Err(InterpreterError {
+                message: format!(
+                    "{} command only accepts object or string parameter",
+                    command
+                ),
+            })
+        }
+    };
+
+    to_interpter_error!(from_document(options))
+}
+
+// Names are listed in `SUB_COMMANDS` of the standard library for the LSP diagnostics
 impl TryFrom<(String, ParametersExpression)> for SubCommand {
     type Error = InterpreterError;
 
@@ -618,7 +1819,29 @@ impl TryFrom<(String, ParametersExpression)> for SubCommand {
 
                 Ok(SubCommand::AllowDiskUse)
             }
-            "explain" => Ok(SubCommand::Explain),
+            "explain" => {
+                if params.params.len() > 1 {
+                    return Err(InterpreterError {
+                        message: "Explain command only accepts 1 parameter".to_string(),
+                    });
+                }
+                if params.params.is_empty() {
+                    return Ok(SubCommand::Explain(EXPLAIN_VERBOSITIES[0].to_string()));
+                }
+
+                let verbosity = try_from!(<String>(params.get_nth_of_type::<Literal>(0)?))?;
+                if !EXPLAIN_VERBOSITIES.contains(&verbosity.as_str()) {
+                    return Err(InterpreterError {
+                        message: format!(
+                            "Explain verbosity must be one of {}, got '{}'",
+                            EXPLAIN_VERBOSITIES.join(", "),
+                            verbosity
+                        ),
+                    });
+                }
+
+                Ok(SubCommand::Explain(verbosity))
+            }
             "skip" => {
                 if params.params.len() > 1 {
                     return Err(InterpreterError {
@@ -643,6 +1866,18 @@ impl TryFrom<(String, ParametersExpression)> for SubCommand {
 
                 Ok(SubCommand::Limit(Some(amount)))
             }
+            "maxtimems" => {
+                if params.params.len() != 1 {
+                    return Err(InterpreterError {
+                        message: "MaxTimeMS command requires exactly 1 parameter".to_string(),
+                    });
+                }
+
+                let millis: u64 =
+                    try_from!(<Number>(params.get_nth_of_type::<Literal>(0)?))?.into();
+
+                Ok(SubCommand::MaxTimeMS(Duration::from_millis(millis)))
+            }
             "hint" => {
                 if params.params.len() > 1 {
                     return Err(InterpreterError {
@@ -661,6 +1896,59 @@ impl TryFrom<(String, ParametersExpression)> for SubCommand {
                     message: "Hint command only accepts object or string parameter".to_string(),
                 })
             }
+            "project" => {
+                if params.params.len() != 1 {
+                    return Err(InterpreterError {
+                        message: "Project command requires exactly 1 parameter".to_string(),
+                    });
+                }
+
+                let projection = params.get_nth_of_type::<ObjectExpression>(0)?;
+                if projection.properties.is_empty() {
+                    return Err(InterpreterError {
+                        message: "Project command requires at least one field".to_string(),
+                    });
+                }
+
+                Ok(SubCommand::Project(to_interpter_error!(to_document(
+                    &projection
+                ))?))
+            }
+            "tail" => {
+                if !params.params.is_empty() {
+                    return Err(InterpreterError {
+                        message: "Tail command doesn't accept any parameter".to_string(),
+                    });
+                }
+
+                Ok(SubCommand::Tail)
+            }
+            "comment" => {
+                if params.params.len() != 1 {
+                    return Err(InterpreterError {
+                        message: "Comment command requires exactly 1 parameter".to_string(),
+                    });
+                }
+
+                match params.get_nth_of_type::<Identifier>(0)? {
+                    Identifier::Literal(Literal::String(comment)) => {
+                        Ok(SubCommand::Comment(comment))
+                    }
+                    _ => Err(InterpreterError {
+                        message: "Comment command only accepts string parameter".to_string(),
+                    }),
+                }
+            }
+            "readconcern" => Ok(SubCommand::ReadConcern(get_options_parameter(
+                "ReadConcern",
+                &params,
+                "level",
+            )?)),
+            "readpreference" => Ok(SubCommand::ReadPreference(get_options_parameter(
+                "ReadPreference",
+                &params,
+                "mode",
+            )?)),
             _ => Err(InterpreterError {
                 message: "Unknown subcommand".to_string(),
             }),
@@ -672,25 +1960,39 @@ impl MongodbConnector {
     pub fn get_handle(&self) -> Database {
         self.client.database(&self.database)
     }
+
+    fn set_collections_listed(&self) {
+        *self.collections_listed_at.lock().unwrap() = Instant::now();
+    }
+}
+
+/// Collections are listed for the completion in the query file of the host and database, the
+/// last listed ones are also kept for files that don't belong to any connection
+fn write_collections(host: &str, database: &str, collections: &[String]) -> Result<()> {
+    let content = collections
+        .iter()
+        .fold(String::new(), |acc, name| acc + name + "\n");
+    write_file_atomically(&get_collections_file(host, database), &content)?;
+    write_file_atomically(&MONGO_COLLECTIONS_FILE, &content)?;
+
+    Ok(())
 }
 
 #[async_trait]
 impl Connector for MongodbConnector {
     async fn set_database(&mut self, database: &str) -> Result<()> {
-        self.database = String::from(database);
-
+        // Listing the collections also verifies the database is accessible before switching to it
         let collections = self
             .client
             .database(database)
             .list_collection_names(None)
             .await
-            .unwrap()
-            .iter()
-            .fold(String::new(), |acc, name| acc + name + "\n");
+            .with_context(|| format!("Failed to switch to database '{}'", database))?;
+        write_collections(&self.info.host, database, &collections)?;
+        self.set_collections_listed();
 
-        let mut file = File::create(MONGO_COLLECTIONS_FILE.to_string())?;
-        file.write_all(collections.as_bytes())?;
-        file.flush()?;
+        self.database = String::from(database);
+        self.info.database = String::from(database);
 
         Ok(())
     }
@@ -699,24 +2001,111 @@ impl Connector for MongodbConnector {
         &self.info
     }
 
-    async fn get_data(&self, str: String, pagination: PaginationInfo) -> Result<DatabaseData> {
-        match InterpreterMongo::new(self, pagination)
-            .interpret(str.to_string())
+    async fn describe(&self, name: &str) -> Result<DatabaseData> {
+        let mut cursor = self
+            .client
+            .database(&self.database)
+            .collection::<Document>(name)
+            .aggregate([doc! {"$sample": {"size": DESCRIBE_SAMPLE_SIZE}}], None)
             .await
-        {
+            .map_err(to_connector_error)?;
+        let mut documents = Vec::new();
+        while cursor.advance().await.map_err(to_connector_error)? {
+            documents.push(cursor.deserialize_current()?);
+        }
+        if documents.is_empty() {
+            return Err(anyhow!("Collection '{}' is empty or doesn't exist", name));
+        }
+
+        infer_schema(&documents)
+            .into_iter()
+            .map(|row| match DatabaseValue::try_from(row) {
+                Ok(DatabaseValue::Object(object)) => Ok(object),
+                _ => Err(anyhow!("Failed to describe collection '{}'", name)),
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(DatabaseData)
+    }
+
+    async fn get_data(
+        &self,
+        str: String,
+        pagination: PaginationInfo,
+        on_batch: OnBatch,
+    ) -> Result<DatabaseData> {
+        if CLI_ARGS.dry_run {
+            return InterpreterMongo::new(self, pagination, &on_batch)
+                .dry_run(str)
+                .map_err(|err| anyhow!(err.message));
+        }
+
+        let is_expired = self.collections_listed_at.lock().unwrap().elapsed() > COLLECTIONS_TTL;
+        if is_expired {
+            // Stale completion is no reason to fail the query
+            if let Err(err) = self.refresh_collections().await {
+                DEBUG_FILE.write_log(&format!("Failed to list collections: {:?}", err));
+            }
+        }
+
+        let mut interpreter = InterpreterMongo::new(self, pagination, &on_batch);
+        match interpreter.interpret(str.to_string()).await {
             Ok(result) => Ok(result),
+            Err(err) if interpreter.is_retryable() => Err(TransientError(err.message).into()),
+            Err(err) => Err(anyhow!(err.message)),
+        }
+    }
+
+    async fn count_documents(&self, str: String) -> Result<Option<u64>> {
+        // Counting would query the collection
+        if CLI_ARGS.dry_run {
+            return Ok(None);
+        }
+        let on_batch: OnBatch = Box::new(|_| {});
+        let pagination = PaginationInfo {
+            start: 0,
+            limit: get_page_size(),
+        };
+
+        let mut interpreter = InterpreterMongo::new(self, pagination, &on_batch);
+        match interpreter.count_documents(str).await {
+            Ok(count) => Ok(count),
+            Err(err) if interpreter.is_retryable() => Err(TransientError(err.message).into()),
             Err(err) => Err(anyhow!(err.message)),
         }
     }
 
+    async fn ping(&self) -> Result<()> {
+        self.client
+            .database("admin")
+            .run_command(doc! {"ping": 1}, None)
+            .await
+            .map_err(to_connector_error)?;
+
+        Ok(())
+    }
+
+    async fn refresh_collections(&self) -> Result<usize> {
+        let collections = self
+            .get_handle()
+            .list_collection_names(None)
+            .await
+            .map_err(to_connector_error)?;
+        write_collections(&self.info.host, &self.database, &collections)?;
+        self.set_collections_listed();
+
+        Ok(collections.len())
+    }
+
     async fn set_connection(&mut self, uri: String) -> Result<ConnectorInfo> {
         let mut client_opts = ClientOptions::parse(uri.clone()).await?;
-        client_opts.server_selection_timeout = Some(Duration::from_secs(3));
+        set_timeouts(&mut client_opts);
+        set_password(&mut client_opts, None)?;
         let client = Client::with_options(client_opts.clone())?;
         client
             .database("admin")
             .run_command(doc! {"ping": 1}, None)
             .await
+            .map_err(to_connector_error)
             .with_context(|| "Failed to connect to the database")?;
 
         let info = ConnectorInfo {
@@ -732,13 +2121,9 @@ impl Connector for MongodbConnector {
         let collections = client
             .database(&info.database)
             .list_collection_names(None)
-            .await?
-            .iter()
-            .fold(String::new(), |acc, name| acc + name + "\n");
-
-        let mut file = File::create(MONGO_COLLECTIONS_FILE.to_string()).unwrap();
-        file.write_all(collections.as_bytes())?;
-        file.flush()?;
+            .await?;
+        write_collections(&info.host, &info.database, &collections)?;
+        self.set_collections_listed();
 
         //self.client.shutdown().await; -- may be needed?
 
@@ -796,12 +2181,386 @@ impl TryFrom<Bson> for DatabaseValue {
             Bson::Double(num) => Ok(DatabaseValue::Number(Number::F64(num))),
             Bson::Int32(num) => Ok(DatabaseValue::Number(Number::I32(num))),
             Bson::Int64(num) => Ok(DatabaseValue::Number(Number::I64(num))),
+            Bson::Decimal128(decimal) => Ok(DatabaseValue::Decimal128(decimal)),
             Bson::Timestamp(timestamp) => Ok(DatabaseValue::DateTime(
                 chrono::Utc.timestamp_opt(timestamp.time as i64, 0).unwrap(),
             )),
             Bson::DateTime(date_time) => Ok(DatabaseValue::DateTime(date_time.into())),
             Bson::ObjectId(object_id) => Ok(DatabaseValue::ObjectId(object_id)),
+            Bson::Binary(binary) => Ok(DatabaseValue::String(binary_to_string(binary))),
             _ => Ok(DatabaseValue::String(value.to_string())),
         }
     }
 }
+
+fn binary_to_string(binary: Binary) -> String {
+    if matches!(binary.subtype, BinarySubtype::Uuid | BinarySubtype::UuidOld) {
+        if let Ok(bytes) = <[u8; 16]>::try_from(binary.bytes.as_slice()) {
+            return Uuid::from_bytes(bytes).to_string();
+        }
+    }
+
+    format!(
+        "{} ({} bytes)",
+        STANDARD.encode(&binary.bytes),
+        binary.bytes.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rusty_db_cli_mongo::{
+        lexer::Lexer,
+        parser::{Expression, Parser},
+        types::expressions::CallExpression,
+    };
+
+    use super::*;
+
+    /// Parameters of the last call in the query, e.g. `1` of `db.c.find().limit(1)`
+    fn parse_last_params(query: &str) -> ParametersExpression {
+        let tokens = Lexer::new(query.to_string()).scan_tokens().unwrap();
+        let mut program = Parser::new(tokens).parse().unwrap();
+
+        match program.body.remove(0) {
+            Expression::ExpressionStatement(statement) => match statement.expression {
+                CallExpression::Recursive(_, params) => params,
+                CallExpression::Primary(call) => call.params,
+                expression => panic!("Expected call expression, got {:?}", expression),
+            },
+            expression => panic!("Expected expression statement, got {:?}", expression),
+        }
+    }
+
+    fn parse_hint(query: &str) -> Option<mongodb::options::Hint> {
+        match SubCommand::try_from(("hint".to_string(), parse_last_params(query))) {
+            Ok(SubCommand::Hint(hint)) => hint,
+            result => panic!("Expected hint, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn hint_index_name() {
+        let name = Some(mongodb::options::Hint::Name("name_1".to_string()));
+
+        assert_eq!(parse_hint(r#"db.c.find({}).hint("name_1")"#), name);
+        assert_eq!(parse_hint("db.c.find({}).hint('name_1')"), name);
+        assert_eq!(parse_hint("db.c.find({}).hint(name_1)"), name);
+    }
+
+    #[test]
+    fn hint_index_keys() {
+        assert_eq!(
+            parse_hint("db.c.find({}).hint({a: 1})"),
+            Some(mongodb::options::Hint::Keys(doc! {"a": 1}))
+        );
+    }
+
+    #[test]
+    fn tail_has_no_parameters() {
+        let parse_tail =
+            |query| SubCommand::try_from(("tail".to_string(), parse_last_params(query)));
+
+        assert!(matches!(
+            parse_tail("db.logs.find().tail()"),
+            Ok(SubCommand::Tail)
+        ));
+        assert!(parse_tail("db.logs.find().tail(10)").is_err());
+    }
+
+    #[test]
+    fn tail_and_watch_are_endless() {
+        let tail = Command::Find(FindQuery {
+            tail: true,
+            ..Default::default()
+        });
+
+        assert!(tail.is_endless());
+        assert!(Command::Watch(WatchQuery::default()).is_endless());
+        assert!(!Command::Find(FindQuery::default()).is_endless());
+    }
+
+    #[test]
+    fn comment_is_single_string() {
+        let parse_comment =
+            |query| SubCommand::try_from(("comment".to_string(), parse_last_params(query)));
+
+        assert!(matches!(
+            parse_comment("db.users.find().comment(\"slow report\")"),
+            Ok(SubCommand::Comment(comment)) if comment == "slow report"
+        ));
+        assert!(parse_comment("db.users.find().comment(5)").is_err());
+        assert!(parse_comment("db.users.find().comment({a: 1})").is_err());
+        assert!(parse_comment("db.users.find().comment(\"a\", \"b\")").is_err());
+        assert!(parse_comment("db.users.find().comment()").is_err());
+    }
+
+    #[test]
+    fn watch_pipeline_is_optional() {
+        let parse_watch = |query| get_watch_pipeline(&parse_last_params(query));
+
+        assert_eq!(
+            parse_watch("db.logs.watch([{$match: {operationType: 'insert'}}])").unwrap(),
+            vec![doc! {"$match": {"operationType": "insert"}}]
+        );
+        assert_eq!(parse_watch("db.logs.watch()").unwrap(), Vec::new());
+        assert!(parse_watch("db.logs.watch({})").is_err());
+    }
+
+    const PAGINATION: PaginationInfo = PaginationInfo {
+        start: 0,
+        limit: 100,
+    };
+
+    #[test]
+    fn aggregate_is_paginated() {
+        let mut query = AggregateQuery {
+            pipelines: vec![doc! {"$match": {"a": 1}}],
+            ..Default::default()
+        };
+        query.paginate(PAGINATION);
+
+        assert_eq!(
+            query.pipelines,
+            vec![
+                doc! {"$match": {"a": 1}},
+                doc! {"$skip": 0},
+                doc! {"$limit": 100_i64}
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregate_with_merge_is_not_paginated() {
+        let pipelines = vec![
+            doc! {"$match": {"a": 1}},
+            doc! {"$merge": {"into": "target"}},
+        ];
+        let mut query = AggregateQuery {
+            pipelines: pipelines.clone(),
+            ..Default::default()
+        };
+        query.paginate(PAGINATION);
+
+        assert_eq!(query.pipelines, pipelines);
+    }
+
+    #[test]
+    fn aggregate_with_count_is_not_paginated() {
+        let pipelines = vec![doc! {"$match": {"a": 1}}, doc! {"$count": "total"}];
+        let mut query = AggregateQuery {
+            pipelines: pipelines.clone(),
+            ..Default::default()
+        };
+        query.paginate(PaginationInfo {
+            start: 100,
+            limit: 100,
+        });
+
+        assert_eq!(query.pipelines, pipelines);
+    }
+
+    #[test]
+    fn aggregate_with_single_group_is_not_paginated() {
+        let pipelines = vec![doc! {"$group": {"_id": null, "total": {"$sum": "$a"}}}];
+        let mut query = AggregateQuery {
+            pipelines: pipelines.clone(),
+            ..Default::default()
+        };
+        query.paginate(PAGINATION);
+
+        assert_eq!(query.pipelines, pipelines);
+    }
+
+    #[test]
+    fn network_errors_are_transient() {
+        let network_error =
+            mongodb::error::Error::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        let serialization_error = mongodb::error::Error::from(to_document(&1).unwrap_err());
+
+        assert!(is_transient_error(&network_error));
+        assert!(!is_transient_error(&serialization_error));
+    }
+
+    #[tokio::test]
+    async fn typed_password_replaces_placeholder() {
+        let uri = "mongodb://user:<password>@localhost:27017";
+        let mut client_opts = ClientOptions::parse(uri).await.unwrap();
+        set_password(&mut client_opts, Some(String::from("secret"))).unwrap();
+        assert_eq!(
+            client_opts.credential.unwrap().password.as_deref(),
+            Some("secret")
+        );
+
+        let mut client_opts = ClientOptions::parse(uri).await.unwrap();
+        assert!(set_password(&mut client_opts, None).is_err());
+    }
+
+    #[test]
+    fn extended_json_is_parsed() {
+        let params = parse_last_params(
+            "db.users.find({
+                _id: {$oid: '65a1b2c3d4e5f60718293a4b'},
+                $or: [
+                    {createdAt: {$gte: {$date: '2024-01-31T12:00:00Z'}}},
+                    {updatedAt: {$date: {$numberLong: '1706702400000'}}},
+                ],
+            })",
+        );
+        let filter = to_document(&params.get_nth_of_type::<ObjectExpression>(0).unwrap()).unwrap();
+
+        let date = mongodb::bson::DateTime::from_millis(1706702400000);
+        assert_eq!(
+            parse_extended_json(filter).unwrap(),
+            doc! {
+                "_id": ObjectId::parse_str("65a1b2c3d4e5f60718293a4b").unwrap(),
+                "$or": [{"createdAt": {"$gte": date}}, {"updatedAt": date}],
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_extended_json_is_reported() {
+        assert!(parse_extended_json(doc! {"_id": {"$oid": "abc"}}).is_err());
+        assert!(parse_extended_json(doc! {"at": {"$date": "yesterday"}}).is_err());
+        // Objects with more keys are left as they are
+        assert_eq!(
+            parse_extended_json(doc! {"a": {"$oid": "abc", "b": 1}}).unwrap(),
+            doc! {"a": {"$oid": "abc", "b": 1}}
+        );
+    }
+
+    #[test]
+    fn valid_lookups() {
+        let lookups = [
+            doc! {"from": "orders", "localField": "_id", "foreignField": "userId", "as": "orders"},
+            doc! {"from": "orders", "let": {"id": "$_id"}, "pipeline": [], "as": "orders"},
+            doc! {"pipeline": [{"$documents": [{"a": 1}]}], "as": "documents"},
+        ];
+
+        for lookup in lookups {
+            assert!(validate_lookup(&Bson::Document(lookup), "$lookup").is_ok());
+        }
+    }
+
+    #[test]
+    fn lookup_reports_missing_key() {
+        let message = |lookup: Document| {
+            validate_lookup(&Bson::Document(lookup), "$lookup")
+                .unwrap_err()
+                .message
+        };
+
+        assert_eq!(
+            message(doc! {"from": "orders", "localField": "_id", "as": "orders"}),
+            "Invalid $lookup stage, missing 'foreignField'"
+        );
+        assert_eq!(
+            message(doc! {"localField": "_id", "foreignField": "userId", "as": "orders"}),
+            "Invalid $lookup stage, missing 'from'"
+        );
+        assert_eq!(
+            message(doc! {"from": "orders", "let": {"id": "$_id"}, "as": "orders"}),
+            "Invalid $lookup stage, missing 'pipeline'"
+        );
+        assert_eq!(
+            message(doc! {
+                "from": "orders",
+                "pipeline": [{"$lookup": {"from": "items", "as": "items"}}],
+                "as": "orders",
+            }),
+            "Invalid $lookup.pipeline.0.$lookup stage, either 'localField' with 'foreignField' or 'pipeline' is required"
+        );
+    }
+
+    #[test]
+    fn valid_expr_and_json_schema() {
+        let filter = doc! {
+            "$expr": {"$gt": ["$spent", {"$multiply": ["$budget", 2]}]},
+            "$jsonSchema": {"required": ["name"]},
+            "$or": [{"$expr": "$isActive"}, {"$expr": true}],
+        };
+
+        assert!(validate_filter(&filter, "").is_ok());
+    }
+
+    #[test]
+    fn invalid_expr_reports_its_path() {
+        let message = |filter: Document| validate_filter(&filter, "").unwrap_err().message;
+
+        assert!(message(doc! {"$expr": 5}).starts_with("Invalid $expr at '$expr'"));
+        assert!(message(doc! {"$and": [{"a": 1}, {"$expr": "a > b"}]})
+            .starts_with("Invalid $expr at '$and.1.$expr'"));
+        assert!(
+            message(doc! {"$expr": {"$gt": ["$a", 1], "$lt": ["$a", 5]}})
+                .starts_with("Invalid expression at '$expr'")
+        );
+        assert!(message(doc! {"$jsonSchema": "schema"})
+            .starts_with("Invalid $jsonSchema at '$jsonSchema'"));
+    }
+
+    fn get_driver_call_lines(command: Command) -> Vec<String> {
+        command
+            .get_driver_call("shop", "orders")
+            .into_iter()
+            .map(|row| match row.get("driver call") {
+                Some(DatabaseValue::String(line)) => line.clone(),
+                value => panic!("Expected driver call line, got {:?}", value),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn find_driver_call() {
+        let mut find = FindQuery {
+            filter: Some(doc! {"status": "paid"}),
+            ..Default::default()
+        };
+        find.add_sub_query(SubCommand::Limit(Some(5))).unwrap();
+        let lines = get_driver_call_lines(Command::Find(find));
+
+        assert_eq!(
+            lines[..3],
+            ["database: shop", "collection: orders", "Find("]
+        );
+        assert!(lines
+            .iter()
+            .any(|line| line.trim() == "\"status\": String("));
+        assert!(lines.iter().any(|line| line.trim() == "limit: Some("));
+        assert!(!lines.iter().any(|line| line.trim() == "skip: None,"));
+    }
+
+    #[test]
+    fn aggregate_driver_call() {
+        let aggregate = AggregateQuery {
+            pipelines: vec![
+                doc! {"$match": {"status": "paid"}},
+                doc! {"$count": "total"},
+            ],
+            ..Default::default()
+        };
+        let lines = get_driver_call_lines(Command::Aggregate(aggregate));
+
+        assert_eq!(
+            lines[..3],
+            ["database: shop", "collection: orders", "Aggregate("]
+        );
+        assert!(lines
+            .iter()
+            .any(|line| line.trim() == "\"$match\": Document({"));
+        assert!(lines
+            .iter()
+            .any(|line| line.trim() == "\"$count\": String("));
+    }
+
+    #[test]
+    fn system_variables() {
+        let stage = |variable: &str| Bson::Document(doc! {"$addFields": {"value": variable}});
+
+        assert!(validate_system_variables(&stage("$$USER_ROLES.role")).is_ok());
+        assert!(validate_system_variables(&stage("$$SEARCH_META")).is_ok());
+        assert!(validate_system_variables(&stage("$$NOW")).is_ok());
+        assert!(validate_system_variables(&stage("$$value")).is_ok());
+        assert!(validate_system_variables(&stage("$$USER_ROLE")).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use mongodb::bson::{doc, Bson, Document};
+
+/// Fields of `executionStats` shown as rows, in this order
+const EXECUTION_STATS_FIELDS: [&str; 4] = [
+    "nReturned",
+    "executionTimeMillis",
+    "totalKeysExamined",
+    "totalDocsExamined",
+];
+
+/// Turns output of the explain command into labeled `field`/`value` rows, other values are kept
+/// as they are
+pub fn format_explain(bson: Bson) -> Vec<Bson> {
+    let Bson::Document(explain) = bson else {
+        return vec![bson];
+    };
+    let Some(query_planner) = find_section(&explain, "queryPlanner") else {
+        return vec![Bson::Document(explain)];
+    };
+
+    let mut rows = Vec::new();
+    if let Ok(namespace) = query_planner.get_str("namespace") {
+        rows.push(("namespace", Bson::String(namespace.to_string())));
+    }
+
+    if let Ok(winning_plan) = query_planner.get_document("winningPlan") {
+        // Slot based engine nests the plan once more
+        let winning_plan = winning_plan
+            .get_document("queryPlan")
+            .unwrap_or(winning_plan);
+        let stages = get_stages(winning_plan);
+
+        rows.push((
+            "winningPlan",
+            Bson::String(
+                stages
+                    .iter()
+                    .filter_map(|stage| stage.get_str("stage").ok())
+                    .collect::<Vec<_>>()
+                    .join(" > "),
+            ),
+        ));
+        rows.push(("indexes", get_index_usage(&stages)));
+    }
+
+    if let Ok(rejected_plans) = query_planner.get_array("rejectedPlans") {
+        rows.push(("rejectedPlans", Bson::Int64(rejected_plans.len() as i64)));
+    }
+
+    if let Some(execution_stats) = find_section(&explain, "executionStats") {
+        for field in EXECUTION_STATS_FIELDS {
+            if let Some(value) = execution_stats.get(field) {
+                rows.push((field, value.clone()));
+            }
+        }
+    }
+
+    // Whole output is still available, e.g. to open it in the editor
+    rows.push(("raw", Bson::Document(explain.clone())));
+
+    rows.into_iter()
+        .map(|(field, value)| Bson::Document(doc! {"field": field, "value": value}))
+        .collect()
+}
+
+/// Find explains have the sections at the top level, aggregations that can use the find layer
+/// nest them in the `$cursor` of the first stage
+fn find_section<'a>(explain: &'a Document, name: &str) -> Option<&'a Document> {
+    explain.get_document(name).ok().or_else(|| {
+        explain
+            .get_array("stages")
+            .ok()?
+            .first()?
+            .as_document()?
+            .get_document("$cursor")
+            .ok()?
+            .get_document(name)
+            .ok()
+    })
+}
+
+/// Stages of the plan from the last one executed to the first one
+fn get_stages(plan: &Document) -> Vec<&Document> {
+    let mut stages = vec![plan];
+    let mut stage = plan;
+    while let Some(input) = stage.get_document("inputStage").ok().or_else(|| {
+        stage
+            .get_array("inputStages")
+            .ok()
+            .and_then(|inputs| inputs.first())
+            .and_then(Bson::as_document)
+    }) {
+        stages.push(input);
+        stage = input;
+    }
+
+    stages
+}
+
+fn get_index_usage(stages: &[&Document]) -> Bson {
+    let indexes = stages
+        .iter()
+        .filter_map(|stage| stage.get_str("indexName").ok())
+        .collect::<Vec<_>>();
+
+    if !indexes.is_empty() {
+        return Bson::String(indexes.join(", "));
+    }
+
+    if stages
+        .iter()
+        .any(|stage| stage.get_str("stage").is_ok_and(|name| name == "COLLSCAN"))
+    {
+        Bson::String(String::from("none (COLLSCAN)"))
+    } else {
+        Bson::String(String::from("none"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explain_is_formatted_into_rows() {
+        let explain = doc! {
+            "queryPlanner": {
+                "namespace": "test.users",
+                "winningPlan": {
+                    "stage": "FETCH",
+                    "inputStage": {"stage": "IXSCAN", "indexName": "age_1"},
+                },
+                "rejectedPlans": [],
+            },
+            "executionStats": {"nReturned": 2, "executionTimeMillis": 1},
+        };
+
+        let rows = format_explain(Bson::Document(explain))
+            .into_iter()
+            .filter_map(|row| {
+                let row = row.as_document()?.clone();
+                Some((
+                    row.get_str("field").ok()?.to_string(),
+                    row.get("value")?.clone(),
+                ))
+            })
+            .filter(|(field, _)| field != "raw")
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "namespace".to_string(),
+                    Bson::String("test.users".to_string())
+                ),
+                (
+                    "winningPlan".to_string(),
+                    Bson::String("FETCH > IXSCAN".to_string())
+                ),
+                ("indexes".to_string(), Bson::String("age_1".to_string())),
+                ("rejectedPlans".to_string(), Bson::Int64(0)),
+                ("nReturned".to_string(), Bson::Int32(2)),
+                ("executionTimeMillis".to_string(), Bson::Int32(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn other_documents_are_kept() {
+        let document = Bson::Document(doc! {"a": 1});
+
+        assert_eq!(format_explain(document.clone()), vec![document]);
+    }
+}
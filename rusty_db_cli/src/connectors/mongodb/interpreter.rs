@@ -1,20 +1,32 @@
 use std::collections::HashMap;
 
-use mongodb::{bson::Document, Database};
+use mongodb::{
+    bson::{doc, to_bson, to_document, Bson, Document},
+    Database,
+};
 use rusty_db_cli_mongo::{
     interpreter::{Interpreter, InterpreterError},
     parser::Expression,
     to_interpter_error,
     types::{
-        expressions::{CallExpression, Callee, Identifier, MemberExpression, ParametersExpression},
+        expressions::{
+            CallExpression, Callee, Identifier, MemberExpression, ObjectExpression,
+            ParametersExpression,
+        },
         literals::Literal,
     },
 };
 use tokio_stream::StreamExt;
 
-use super::connector::{DatabaseResponse, MongodbConnector, SubCommand};
+use super::{
+    connector::{
+        ensure_destructive_allowed, ensure_finite, ensure_write_allowed, is_transient_error,
+        to_driver_error, DatabaseResponse, MongodbConnector, SubCommand,
+    },
+    explain::format_explain,
+};
 use crate::connectors::{
-    base::{DatabaseData, DatabaseValue, Object, PaginationInfo},
+    base::{DatabaseData, DatabaseValue, Object, OnBatch, PaginationInfo},
     mongodb::connector::{Command, QueryBuilder},
 };
 
@@ -22,6 +34,11 @@ pub struct InterpreterMongo<'a> {
     connector: &'a MongodbConnector,
     expressions: Vec<Expression>,
     pagination: PaginationInfo,
+    on_batch: &'a OnBatch,
+    /// The query writes, it must not be run again after a failure
+    is_write: bool,
+    /// The query failed on an error that may not happen again
+    is_retryable: bool,
 }
 
 #[macro_export]
@@ -37,18 +54,97 @@ macro_rules! try_from {
     }};
 }
 
-const MAXIMUM_DOCUMENTS: usize = 100;
+/// Amount of documents after which the fetched documents are sent to the table
+const BATCH_SIZE: usize = 25;
+
+/// Fields of the change events shown as columns, the rest is mostly internal to the server
+const CHANGE_EVENT_FIELDS: [&str; 4] = [
+    "operationType",
+    "documentKey",
+    "fullDocument",
+    "updateDescription",
+];
+
+/// Methods of `db` itself, anything else following `db.` is a collection name
+const DATABASE_METHODS: [&str; 4] = ["getCollectionNames", "runCommand", "currentOp", "killOp"];
 
 impl<'a> InterpreterMongo<'a> {
-    pub fn new(connector: &'a MongodbConnector, pagination: PaginationInfo) -> Self {
+    pub fn new(
+        connector: &'a MongodbConnector,
+        pagination: PaginationInfo,
+        on_batch: &'a OnBatch,
+    ) -> Self {
         Self {
             connector,
             expressions: vec![],
             pagination,
+            on_batch,
+            is_write: false,
+            is_retryable: false,
+        }
+    }
+
+    pub async fn interpret(&mut self, data: String) -> Result<DatabaseData, InterpreterError> {
+        self.resolve_program(data)?;
+        self.execute_db_call().await
+    }
+
+    /// Counts all documents matching a find query, other queries have no count
+    pub async fn count_documents(&mut self, data: String) -> Result<Option<u64>, InterpreterError> {
+        self.resolve_program(data)?;
+        if self.try_get_next_literal::<String>()? != "db" {
+            return Ok(None);
         }
+
+        let (db, collection_name) = self.resolve_database()?;
+        if DATABASE_METHODS.contains(&collection_name.as_str()) {
+            return Ok(None);
+        }
+
+        let Command::Find(find) = self.parse_command_expression()? else {
+            return Ok(None);
+        };
+        let collection = db.collection(&collection_name);
+
+        find.count_documents(collection)
+            .await
+            .map_err(|err| self.driver_error(err))
     }
 
-    pub async fn interpret(mut self, data: String) -> Result<DatabaseData, InterpreterError> {
+    /// Driver call the query translates into, listed line by line without running it. Database
+    /// methods are sent to the server as they are, so only collection commands are translated
+    pub fn dry_run(&mut self, data: String) -> Result<DatabaseData, InterpreterError> {
+        self.resolve_program(data)?;
+        if self.try_get_next_literal::<String>()? != "db" {
+            return Err(InterpreterError {
+                message: "Failed to execute db call".to_string(),
+            });
+        }
+
+        let (db, collection_name) = self.resolve_database()?;
+        if DATABASE_METHODS.contains(&collection_name.as_str()) {
+            return Err(InterpreterError {
+                message: format!("db.{}() is not shown in dry run mode", collection_name),
+            });
+        }
+        let command = self.parse_command_expression()?;
+
+        Ok(command.get_driver_call(db.name(), &collection_name))
+    }
+
+    /// Whether the query failed on a network error or similar and didn't write anything, so it
+    /// can be run again
+    pub fn is_retryable(&self) -> bool {
+        self.is_retryable
+    }
+
+    fn driver_error(&mut self, err: mongodb::error::Error) -> InterpreterError {
+        self.is_retryable = !self.is_write && is_transient_error(&err);
+        to_driver_error(err)
+    }
+
+    /// Resolves the first expression of the program into the expressions to consume
+    fn resolve_program(&mut self, data: String) -> Result<(), InterpreterError> {
         let mut program = Interpreter::new().tokenize(data).parse()?;
         // Our parser performs reverse-ordered tokenization and parsing,
         // -> it constructs an output array where tokens are stored in reverse order
@@ -56,46 +152,56 @@ impl<'a> InterpreterMongo<'a> {
         // first line first, so we reverse the array.
         program.body.reverse();
 
-        if let Some(expression) = program.body.pop() {
-            return match expression {
-                Expression::ExpressionStatement(expression_statement) => {
-                    return self
-                        .execute_call_expression(expression_statement.expression)
-                        .await;
+        match program.body.pop() {
+            Some(Expression::ExpressionStatement(expression_statement)) => {
+                self.resolve_call_expression(expression_statement.expression);
+                if self.expressions.is_empty() {
+                    return Err(InterpreterError {
+                        message: "Empty call expression".to_string(),
+                    });
                 }
-                _ => {
-                    // Program should not ever have another Program in it
-                    Err(InterpreterError {
-                        message: "Program should only have one expression".to_string(),
-                    })
-                }
-            };
+                Ok(())
+            }
+            // Program should not ever have another Program in it
+            Some(_) => Err(InterpreterError {
+                message: "Program should only have one expression".to_string(),
+            }),
+            None => Err(InterpreterError {
+                message: "Failed to interpret data".to_string(),
+            }),
         }
-
-        Err(InterpreterError {
-            message: "Failed to interpret data".to_string(),
-        })
     }
 
     async fn execute_db_call(&mut self) -> Result<DatabaseData, InterpreterError> {
         if self.try_get_next_literal::<String>()? == "db" {
-            let db = self.connector.get_handle();
-
-            let next_literal = self.try_get_next_literal::<String>()?;
+            let (db, next_literal) = self.resolve_database()?;
 
             let mut result: DatabaseData = DatabaseData(Vec::new());
 
             let database_response = if next_literal == "getCollectionNames" {
-                DatabaseResponse::CursorCollectionSpec(to_interpter_error!(
-                    db.list_collections(None, None).await
-                )?)
+                DatabaseResponse::CursorCollectionSpec(
+                    db.list_collections(None, None)
+                        .await
+                        .map_err(|err| self.driver_error(err))?,
+                )
+            } else if next_literal == "runCommand" {
+                self.execute_run_command(db).await?
+            } else if next_literal == "currentOp" {
+                self.execute_current_op().await?
+            } else if next_literal == "killOp" {
+                self.execute_kill_op().await?
             } else {
                 self.execute_command_expression(&next_literal, db).await?
             };
 
             match database_response {
                 super::connector::DatabaseResponse::Cursor(mut cursor) => {
-                    while let Some(doc) = cursor.try_next().await.unwrap() {
+                    // Fetching next batch can fail as well, e.g. when the query exceeds its max time
+                    while let Some(doc) = cursor
+                        .try_next()
+                        .await
+                        .map_err(|err| self.driver_error(err))?
+                    {
                         let converted_doc = try_from!(<DatabaseValue>(doc))?;
                         match converted_doc {
                             DatabaseValue::Object(obj) => {
@@ -107,13 +213,72 @@ impl<'a> InterpreterMongo<'a> {
                                 })
                             }
                         }
-                        if result.len() >= MAXIMUM_DOCUMENTS {
+                        if result.len() >= self.pagination.limit as usize {
                             break;
                         }
+                        if result.len().is_multiple_of(BATCH_SIZE) {
+                            (self.on_batch)(result.clone());
+                        }
+                    }
+                }
+                DatabaseResponse::TailableCursor(mut cursor) => {
+                    // Only the last page of the documents is kept, every new one is shown as it
+                    // arrives
+                    while let Some(doc) = cursor
+                        .try_next()
+                        .await
+                        .map_err(|err| self.driver_error(err))?
+                    {
+                        match try_from!(<DatabaseValue>(doc))? {
+                            DatabaseValue::Object(obj) => {
+                                result.push(obj);
+                            }
+                            _ => {
+                                return Err(InterpreterError {
+                                    message: "Database returned unexpected value".to_string(),
+                                })
+                            }
+                        }
+                        if result.len() > self.pagination.limit as usize {
+                            result.0.remove(0);
+                        }
+                        (self.on_batch)(result.clone());
+                    }
+                }
+                DatabaseResponse::ChangeStream(mut change_stream) => {
+                    while let Some(event) = change_stream
+                        .try_next()
+                        .await
+                        .map_err(|err| self.driver_error(err))?
+                    {
+                        let mut row = Document::new();
+                        for field in CHANGE_EVENT_FIELDS {
+                            if let Some(value) = event.get(field) {
+                                row.insert(field, value.clone());
+                            }
+                        }
+                        match try_from!(<DatabaseValue>(row))? {
+                            DatabaseValue::Object(obj) => {
+                                result.push(obj);
+                            }
+                            _ => {
+                                return Err(InterpreterError {
+                                    message: "Database returned unexpected value".to_string(),
+                                })
+                            }
+                        }
+                        if result.len() > self.pagination.limit as usize {
+                            result.0.remove(0);
+                        }
+                        (self.on_batch)(result.clone());
                     }
                 }
                 DatabaseResponse::CursorCollectionSpec(mut cursor) => {
-                    while let Some(doc) = cursor.try_next().await.unwrap() {
+                    while let Some(doc) = cursor
+                        .try_next()
+                        .await
+                        .map_err(|err| self.driver_error(err))?
+                    {
                         let converted_doc = DatabaseValue::CollectionInfo(doc);
 
                         match converted_doc {
@@ -129,7 +294,11 @@ impl<'a> InterpreterMongo<'a> {
                     }
                 }
                 super::connector::DatabaseResponse::CursorIndexes(mut cursor) => {
-                    while let Some(doc) = cursor.try_next().await.unwrap() {
+                    while let Some(doc) = cursor
+                        .try_next()
+                        .await
+                        .map_err(|err| self.driver_error(err))?
+                    {
                         let converted_doc = try_from!(<DatabaseValue>(doc))?;
                         match converted_doc {
                             DatabaseValue::Object(obj) => {
@@ -141,13 +310,40 @@ impl<'a> InterpreterMongo<'a> {
                                 })
                             }
                         }
-                        if result.len() >= MAXIMUM_DOCUMENTS {
+                        if result.len() >= self.pagination.limit as usize {
                             break;
                         }
                     }
                 }
+                super::connector::DatabaseResponse::Indexes(indexes) => {
+                    for index in indexes {
+                        match try_from!(<DatabaseValue>(index))? {
+                            DatabaseValue::Object(obj) => {
+                                result.push(obj);
+                            }
+                            _ => {
+                                return Err(InterpreterError {
+                                    message: "Database returned unexpected value".to_string(),
+                                })
+                            }
+                        }
+                    }
+                }
+                DatabaseResponse::Distinct(field, values) => {
+                    // Whole result is returned at once, so the pages are taken from it
+                    for value in values
+                        .into_iter()
+                        .skip(self.pagination.start as usize)
+                        .take(self.pagination.limit as usize)
+                    {
+                        result.push(Object(HashMap::from([(
+                            field.clone(),
+                            try_from!(<DatabaseValue>(value))?,
+                        )])));
+                    }
+                }
                 super::connector::DatabaseResponse::Bson(bson_arr) => {
-                    for bson in bson_arr {
+                    for bson in bson_arr.into_iter().flat_map(format_explain) {
                         let converted_bson = try_from!(<DatabaseValue>(bson))?;
                         match converted_bson {
                             DatabaseValue::Object(obj) => {
@@ -174,6 +370,19 @@ impl<'a> InterpreterMongo<'a> {
         collection_name: &str,
         db: Database,
     ) -> Result<DatabaseResponse, InterpreterError> {
+        let main_command = self.parse_command_expression()?;
+        ensure_finite(&main_command)?;
+        self.is_write = main_command.is_write();
+        let collection: mongodb::Collection<Document> = db.collection(collection_name);
+
+        main_command
+            .build(collection, self.pagination, db)
+            .await
+            .map_err(|err| self.driver_error(err))
+    }
+
+    /// Command with all the sub commands chained after it, e.g. `find({}).limit(1)`
+    fn parse_command_expression(&mut self) -> Result<Command, InterpreterError> {
         let command_type = self.try_get_next_literal::<String>()?;
         let params = self.consume::<ParametersExpression>()?;
         let mut main_command = Command::try_from((command_type, params))?;
@@ -185,12 +394,119 @@ impl<'a> InterpreterMongo<'a> {
             main_command.add_sub_query(SubCommand::try_from((command, params))?)?;
         }
 
-        let collection: mongodb::Collection<Document> = db.collection(collection_name);
+        Ok(main_command)
+    }
 
-        Ok(main_command
-            .build(collection, self.pagination, db)
+    async fn execute_run_command(
+        &mut self,
+        db: Database,
+    ) -> Result<DatabaseResponse, InterpreterError> {
+        // Commands can't be told apart, any of them may write
+        ensure_write_allowed("runCommand")?;
+        self.is_write = true;
+        let params = self.consume::<ParametersExpression>()?;
+        if params.params.len() != 1 {
+            return Err(InterpreterError {
+                message: "RunCommand requires exactly 1 parameter".to_string(),
+            });
+        }
+        let command =
+            to_interpter_error!(to_document(&params.get_nth_of_type::<ObjectExpression>(0)?))?;
+
+        Ok(DatabaseResponse::Bson(vec![Bson::Document(
+            db.run_command(command, None)
+                .await
+                .map_err(|err| self.driver_error(err))?,
+        )]))
+    }
+
+    /// In-progress operations of the server, filtered like `db.currentOp({active: true})`
+    async fn execute_current_op(&mut self) -> Result<DatabaseResponse, InterpreterError> {
+        let params = self.consume::<ParametersExpression>()?;
+        if params.params.len() > 1 {
+            return Err(InterpreterError {
+                message: "CurrentOp command only accepts 1 parameter".to_string(),
+            });
+        }
+        let mut command = doc! {"currentOp": 1};
+        if !params.params.is_empty() {
+            command.extend(to_interpter_error!(to_document(
+                &params.get_nth_of_type::<ObjectExpression>(0)?
+            ))?);
+        }
+
+        let mut response = self
+            .connector
+            .client
+            .database("admin")
+            .run_command(command, None)
             .await
-            .unwrap())
+            .map_err(|err| self.driver_error(err))?;
+        match response.remove("inprog") {
+            Some(Bson::Array(operations)) => Ok(DatabaseResponse::Bson(operations)),
+            _ => Err(InterpreterError {
+                message: "CurrentOp returned no operations".to_string(),
+            }),
+        }
+    }
+
+    /// Terminates the operation with the `opid` listed by `db.currentOp()`
+    async fn execute_kill_op(&mut self) -> Result<DatabaseResponse, InterpreterError> {
+        ensure_destructive_allowed("killOp")?;
+        self.is_write = true;
+        let params = self.consume::<ParametersExpression>()?;
+        if params.params.len() != 1 {
+            return Err(InterpreterError {
+                message: "KillOp requires exactly 1 parameter".to_string(),
+            });
+        }
+        // Operations of a sharded cluster are identified by `<shard>:<opid>` strings
+        let op = match params.get_nth_of_type::<Literal>(0)? {
+            Literal::Number(opid) => to_interpter_error!(to_bson(&opid))?,
+            Literal::String(opid) => Bson::String(opid),
+            _ => {
+                return Err(InterpreterError {
+                    message: "KillOp requires the opid of the operation, a number or a string"
+                        .to_string(),
+                })
+            }
+        };
+
+        Ok(DatabaseResponse::Bson(vec![Bson::Document(
+            self.connector
+                .client
+                .database("admin")
+                .run_command(doc! {"killOp": 1, "op": op}, None)
+                .await
+                .map_err(|err| self.driver_error(err))?,
+        )]))
+    }
+
+    /// Database the query runs on with the literal following it. `db.getSiblingDB("name")`
+    /// switches the database for this query only, the connection stays on its own database
+    fn resolve_database(&mut self) -> Result<(Database, String), InterpreterError> {
+        let next_literal = self.try_get_next_literal::<String>()?;
+        if next_literal != "getSiblingDB" {
+            return Ok((self.connector.get_handle(), next_literal));
+        }
+
+        let params = self.consume::<ParametersExpression>()?;
+        if params.params.len() != 1 {
+            return Err(InterpreterError {
+                message: "GetSiblingDB requires exactly 1 parameter".to_string(),
+            });
+        }
+        let name = try_from!(<String>(params.get_nth_of_type::<Literal>(0)?))?;
+        if name.is_empty() {
+            return Err(InterpreterError {
+                message: "GetSiblingDB requires a database name".to_string(),
+            });
+        }
+
+        Ok((
+            self.connector.client.database(&name),
+            self.try_get_next_literal::<String>()?,
+        ))
     }
 
     fn try_get_next_literal<T: TryFrom<Literal>>(&mut self) -> Result<T, InterpreterError> {
@@ -198,7 +514,12 @@ impl<'a> InterpreterMongo<'a> {
     }
 
     fn consume<T: TryFrom<Expression>>(&mut self) -> Result<T, InterpreterError> {
-        let result = self.expressions.pop().unwrap().extract::<T>();
+        let Some(expression) = self.expressions.pop() else {
+            return Err(InterpreterError {
+                message: "Incomplete query, expected db.<collection>.<command>()".to_string(),
+            });
+        };
+        let result = expression.extract::<T>();
         if let Ok(expression) = result {
             return Ok(expression);
         }
@@ -208,20 +529,6 @@ impl<'a> InterpreterMongo<'a> {
         })
     }
 
-    async fn execute_call_expression(
-        &mut self,
-        call: CallExpression,
-    ) -> Result<DatabaseData, InterpreterError> {
-        self.resolve_call_expression(call);
-
-        if self.expressions.is_empty() {
-            return Err(InterpreterError {
-                message: "Empty call expression".to_string(),
-            });
-        }
-        self.execute_db_call().await
-    }
-
     fn resolve_call_expression(&mut self, call: CallExpression) {
         match call {
             CallExpression::Primary(primary) => {
@@ -1,2 +1,4 @@
 pub mod connector;
+pub mod explain;
 pub mod interpreter;
+pub mod schema;
@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use mongodb::bson::{doc, Bson, Document};
+
+#[derive(Default)]
+struct FieldInfo {
+    types: BTreeSet<&'static str>,
+    /// Documents the field is present in
+    count: usize,
+    has_null: bool,
+}
+
+/// Fields of the sampled documents with the types seen in them, as `field`/`type`/`nullable`
+/// rows. Fields of nested documents are listed as dotted paths, a field is nullable when it's
+/// null or missing in some of the documents
+pub fn infer_schema(documents: &[Document]) -> Vec<Document> {
+    let mut fields = BTreeMap::new();
+    for document in documents {
+        collect_fields(document, "", &mut fields);
+    }
+
+    fields
+        .into_iter()
+        .map(|(field, info)| {
+            let r#type = if info.types.is_empty() {
+                String::from("null")
+            } else {
+                info.types.into_iter().collect::<Vec<_>>().join(" | ")
+            };
+            doc! {
+                "field": field,
+                "type": r#type,
+                "nullable": info.has_null || info.count < documents.len(),
+            }
+        })
+        .collect()
+}
+
+fn collect_fields(document: &Document, prefix: &str, fields: &mut BTreeMap<String, FieldInfo>) {
+    for (key, value) in document {
+        let path = format!("{}{}", prefix, key);
+        let info = fields.entry(path.clone()).or_default();
+        info.count += 1;
+        match value {
+            Bson::Null => info.has_null = true,
+            value => {
+                info.types.insert(get_type_name(value));
+            }
+        }
+
+        if let Bson::Document(nested) = value {
+            collect_fields(nested, &format!("{}.", path), fields);
+        }
+    }
+}
+
+/// Names used by the `$type` operator
+fn get_type_name(value: &Bson) -> &'static str {
+    match value {
+        Bson::Double(_) => "double",
+        Bson::String(_) => "string",
+        Bson::Document(_) => "object",
+        Bson::Array(_) => "array",
+        Bson::Binary(_) => "binData",
+        Bson::ObjectId(_) => "objectId",
+        Bson::Boolean(_) => "bool",
+        Bson::DateTime(_) => "date",
+        Bson::Null => "null",
+        Bson::RegularExpression(_) => "regex",
+        Bson::JavaScriptCode(_) | Bson::JavaScriptCodeWithScope(_) => "javascript",
+        Bson::Int32(_) => "int",
+        Bson::Timestamp(_) => "timestamp",
+        Bson::Int64(_) => "long",
+        Bson::Decimal128(_) => "decimal",
+        Bson::MinKey => "minKey",
+        Bson::MaxKey => "maxKey",
+        _ => "undefined",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_is_inferred_from_documents() {
+        let documents = [
+            doc! {"name": "a", "age": 1, "address": {"city": "Prague"}},
+            doc! {"name": "b", "age": 1.5, "address": null},
+        ];
+
+        assert_eq!(
+            infer_schema(&documents),
+            vec![
+                doc! {"field": "address", "type": "object", "nullable": true},
+                doc! {"field": "address.city", "type": "string", "nullable": true},
+                doc! {"field": "age", "type": "double | int", "nullable": false},
+                doc! {"field": "name", "type": "string", "nullable": false},
+            ]
+        );
+    }
+}
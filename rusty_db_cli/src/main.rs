@@ -1,7 +1,7 @@
 use core::time;
 use std::{
-    io::{self},
-    thread,
+    io::{self, Read},
+    process, thread,
     time::Duration,
 };
 
@@ -13,8 +13,17 @@ use crossterm::{
 use ratatui::{prelude::CrosstermBackend, Terminal};
 use rusty_db_cli::{
     application::wait_for_app_initialization,
+    connectors::{
+        base::{get_page_size, retry, PaginationInfo},
+        factory::build_connector,
+    },
     managers::window_manager::WindowManagerBuilder,
-    ui::layouts::{get_table_layout, CLI_ARGS},
+    ui::layouts::{get_table_layout, OutputFormat, CLI_ARGS},
+    utils::{
+        keymap::Keymap,
+        password::{is_password_prompted, prompt_password},
+        theme::Theme,
+    },
 };
 use tokio::task;
 
@@ -22,6 +31,32 @@ use tokio::task;
 async fn main() {
     CLI_ARGS.debug;
 
+    if let Err(err) = Keymap::load().and(Theme::load()) {
+        eprintln!("{:#}", err);
+        process::exit(1);
+    }
+
+    // Asked before the TUI starts, so it's typed straight into the terminal
+    let password = if is_password_prompted(&CLI_ARGS.database_uri) {
+        match prompt_password("Password: ") {
+            Ok(password) => Some(password),
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    if let Some(query) = &CLI_ARGS.query {
+        if let Err(err) = run_query(query, password).await {
+            eprintln!("{:#}", err);
+            process::exit(1);
+        }
+        return;
+    }
+
     enable_raw_mode().unwrap();
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture).unwrap();
@@ -32,7 +67,7 @@ async fn main() {
     let app = wait_for_app_initialization(
         task::spawn(async {
             WindowManagerBuilder::new()
-                .with_window(get_table_layout().await)
+                .with_window(get_table_layout(password).await)
                 .build()
         }),
         term,
@@ -44,8 +79,10 @@ async fn main() {
         handle.render();
 
         if event::poll(Duration::from_secs(0)).unwrap() {
-            if let Event::Key(key) = event::read().unwrap() {
-                handle.on_key(key);
+            match event::read().unwrap() {
+                Event::Key(key) => handle.on_key(key),
+                Event::Mouse(mouse) => handle.on_mouse(mouse),
+                _ => {}
             }
         }
 
@@ -65,3 +102,48 @@ async fn main() {
     )
     .unwrap();
 }
+
+/// Runs a single query and prints its result, without starting the TUI
+async fn run_query(query: &str, password: Option<String>) -> anyhow::Result<()> {
+    let query = if query == "-" {
+        let mut query = String::new();
+        io::stdin().read_to_string(&mut query)?;
+        query
+    } else {
+        query.to_string()
+    };
+
+    let on_retry = |attempt, err: &anyhow::Error| {
+        eprintln!("{}, retrying ({}/{})", err, attempt, CLI_ARGS.retries)
+    };
+    let connector = build_connector(&CLI_ARGS.database_uri, password, on_retry).await?;
+    let (connector, query) = (&connector, &query);
+
+    let pagination = PaginationInfo {
+        start: 0,
+        limit: get_page_size(),
+    };
+    let data = retry(
+        || async move {
+            let connector = connector.lock().await;
+            connector
+                .get_data(query.clone(), pagination, Box::new(|_| {}))
+                .await
+        },
+        on_retry,
+    )
+    .await?;
+
+    match CLI_ARGS.output {
+        OutputFormat::Json => {
+            println!("{}", data.into_extended_json());
+        }
+        OutputFormat::Ndjson => {
+            for object in data {
+                println!("{}", object.into_extended_json());
+            }
+        }
+    }
+
+    Ok(())
+}
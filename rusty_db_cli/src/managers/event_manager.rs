@@ -7,6 +7,7 @@ use std::{
 };
 
 use anyhow::Result;
+use crossterm::event::MouseEvent;
 use mongodb::event::command::ConnectionInfo;
 use tokio::{task::JoinHandle, time};
 
@@ -17,47 +18,64 @@ use crate::{
         components::{base::Component, command::Message},
         window::OnInputInfo,
     },
+    utils::profiles::ConnectionProfile,
 };
 
 pub enum ConnectionEvent {
     Add(ConnectionInfo),
-    Connect(String),
+    Connect(ConnectionProfile),
     SwitchConnection(String, String),
     SwitchDatabase(String),
+    /// Lists the collections used by the completion again
+    RefreshCollections,
+    /// Result of the periodic ping, `false` while the database can't be reached
+    Health(bool),
 }
 
 pub enum Event {
     OnInput(OnInputInfo),
+    /// Wheel and click of the mouse, sent only in the view mode
+    OnMouse(MouseEvent),
     OnMessage(Message),
     DatabaseData(DatabaseFetchResult),
     OnQuery(String),
+    /// Shows fields of the collection with the given name
+    OnDescribe(String),
     OnWindowCommand(WindowCommand),
     OnConnection(ConnectionEvent),
     OnAsyncEvent(JoinHandle<()>),
+    /// Total amount of documents matching the query, `None` while it's unknown
+    OnDocumentCount(Option<u64>),
 }
 
 #[derive(Eq, Hash, PartialEq, Debug)]
 pub enum EventType {
     OnInput,
+    OnMouse,
     DatabaseData,
     OnQuery,
+    OnDescribe,
     OnWindowCommand,
     OnAuthCommand,
     OnConnection,
     OnMessage,
     AsyncEvent,
+    OnDocumentCount,
 }
 
 impl Event {
     pub fn get_type(&self) -> EventType {
         match self {
             Event::OnInput(_) => EventType::OnInput,
+            Event::OnMouse(_) => EventType::OnMouse,
             Event::DatabaseData(_) => EventType::DatabaseData,
             Event::OnQuery(_) => EventType::OnQuery,
+            Event::OnDescribe(_) => EventType::OnDescribe,
             Event::OnWindowCommand(_) => EventType::OnWindowCommand,
             Event::OnConnection(_) => EventType::OnConnection,
             Event::OnMessage(_) => EventType::OnMessage,
             Event::OnAsyncEvent(_) => EventType::AsyncEvent,
+            Event::OnDocumentCount(_) => EventType::OnDocumentCount,
         }
     }
 }
@@ -31,7 +31,7 @@ impl EventHandler for WindowManager {
                     self.focused_window = value.clone();
                 }
                 WindowCommand::SetFocusedComponent(value) => {
-                    self.get_focused_window().focused_component_idx = value.clone();
+                    self.get_focused_window().set_focused_component(*value);
                 }
             }
         }
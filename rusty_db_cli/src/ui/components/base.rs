@@ -22,6 +22,16 @@ pub trait Component: EventHandler + Send {
     fn is_visible(&self) -> bool;
     fn set_visibility(&mut self, visible: bool) -> bool;
     fn draw(&mut self, info: ComponentDrawInfo);
+    /// Can receive the focus, i.e. the keys of the view mode
+    fn is_focusable(&self) -> bool {
+        false
+    }
+    fn set_focused(&mut self, _focused: bool) {}
+    /// Receives all keys while focused, e.g. when a popup is open, so they don't trigger the
+    /// keybinds of the window or the application
+    fn is_capturing_input(&self) -> bool {
+        false
+    }
     fn as_event_handler(&self) -> &dyn EventHandler
     where
         Self: std::marker::Sized,
@@ -0,0 +1,122 @@
+use std::cmp;
+
+use crossterm::event;
+use ratatui::{
+    layout::Rect,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+use sublime_fuzzy::best_match;
+
+use crate::utils::{fuzzy::filter_fuzzy_matches, theme::THEME};
+
+pub enum ColumnJumpAction {
+    None,
+    /// Scrolls the table to the column
+    Jump(String),
+    Close,
+}
+
+/// Prompt jumping to the column fuzzy matching the typed name, drawn over the table
+#[derive(Default)]
+pub struct ColumnJump {
+    filter: String,
+    selected: usize,
+}
+
+impl ColumnJump {
+    /// Best matches first, so Enter jumps right away most of the time
+    fn get_matches(&self, columns: &[String]) -> Vec<String> {
+        if self.filter.is_empty() {
+            return columns.to_vec();
+        }
+        let mut matches = filter_fuzzy_matches(&self.filter, columns);
+        matches.sort_by_cached_key(|column| {
+            cmp::Reverse(best_match(&self.filter, column).map(|matched| matched.score()))
+        });
+
+        matches
+    }
+
+    /// Typed characters filter the columns, so the list is moved with arrows
+    pub fn on_key(&mut self, key: event::KeyCode, columns: &[String]) -> ColumnJumpAction {
+        let matches = self.get_matches(columns);
+        match key {
+            event::KeyCode::Esc => return ColumnJumpAction::Close,
+            event::KeyCode::Enter => {
+                if let Some(column) = matches.get(self.selected) {
+                    return ColumnJumpAction::Jump(column.clone());
+                }
+            }
+            event::KeyCode::Down => {
+                self.selected = cmp::min(self.selected + 1, matches.len().saturating_sub(1))
+            }
+            event::KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            event::KeyCode::Char(ch) => {
+                self.filter.push(ch);
+                self.selected = 0;
+            }
+            event::KeyCode::Backspace => {
+                self.filter.pop();
+                self.selected = 0;
+            }
+            _ => {}
+        }
+
+        ColumnJumpAction::None
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect, columns: &[String]) {
+        let matches = self.get_matches(columns);
+        let title = format!(" Jump to column: {}█ ", self.filter);
+        let content_width = matches
+            .iter()
+            .map(|column| column.chars().count())
+            .chain([title.chars().count()])
+            .max()
+            .unwrap_or_default() as u16;
+
+        // Borders take two cells in both directions
+        let width = cmp::min(content_width + 2, area.width);
+        let height = cmp::min(matches.len() as u16 + 2, area.height);
+        let jump_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let items = matches.into_iter().map(ListItem::new).collect::<Vec<_>>();
+
+        frame.render_widget(Clear, jump_area);
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .highlight_style(THEME.selection),
+            jump_area,
+            &mut ListState::default().with_selected(Some(self.selected)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_match_is_first() {
+        let columns = ["_id", "username", "createdAt", "name"]
+            .iter()
+            .map(|column| column.to_string())
+            .collect::<Vec<_>>();
+        let mut jump = ColumnJump::default();
+        for ch in "name".chars() {
+            jump.on_key(event::KeyCode::Char(ch), &columns);
+        }
+
+        assert!(matches!(
+            jump.on_key(event::KeyCode::Enter, &columns),
+            ColumnJumpAction::Jump(column) if column == "name"
+        ));
+    }
+}
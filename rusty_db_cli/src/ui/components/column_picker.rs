@@ -0,0 +1,109 @@
+use std::{cmp, collections::HashSet};
+
+use crossterm::event;
+use ratatui::{
+    layout::Rect,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::utils::{fuzzy::filter_fuzzy_matches, theme::THEME};
+
+pub enum ColumnPickerAction {
+    None,
+    /// Shows the column when it's hidden, hides it otherwise
+    Toggle(String),
+    Close,
+}
+
+/// Fuzzy filterable list of the table columns, drawn over the table
+#[derive(Default)]
+pub struct ColumnPicker {
+    filter: String,
+    selected: usize,
+}
+
+impl ColumnPicker {
+    fn get_matches(&self, columns: &[String]) -> Vec<String> {
+        if self.filter.is_empty() {
+            return columns.to_vec();
+        }
+        filter_fuzzy_matches(&self.filter, columns)
+    }
+
+    /// Typed characters filter the columns, so the list is moved with arrows
+    pub fn on_key(&mut self, key: event::KeyCode, columns: &[String]) -> ColumnPickerAction {
+        let matches = self.get_matches(columns);
+        match key {
+            event::KeyCode::Esc => return ColumnPickerAction::Close,
+            event::KeyCode::Enter => {
+                if let Some(column) = matches.get(self.selected) {
+                    return ColumnPickerAction::Toggle(column.clone());
+                }
+            }
+            event::KeyCode::Down => {
+                self.selected = cmp::min(self.selected + 1, matches.len().saturating_sub(1))
+            }
+            event::KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            event::KeyCode::Char(ch) => {
+                self.filter.push(ch);
+                self.selected = 0;
+            }
+            event::KeyCode::Backspace => {
+                self.filter.pop();
+                self.selected = 0;
+            }
+            _ => {}
+        }
+
+        ColumnPickerAction::None
+    }
+
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        columns: &[String],
+        hidden_columns: &HashSet<String>,
+    ) {
+        let matches = self.get_matches(columns);
+        let title = format!(" Columns: {}█ ", self.filter);
+        let content_width = matches
+            .iter()
+            .map(|column| column.chars().count() + 4)
+            .chain([title.chars().count()])
+            .max()
+            .unwrap_or_default() as u16;
+
+        // Borders take two cells in both directions
+        let width = cmp::min(content_width + 2, area.width);
+        let height = cmp::min(matches.len() as u16 + 2, area.height);
+        let picker_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let items = matches
+            .into_iter()
+            .map(|column| {
+                let checkbox = if hidden_columns.contains(&column) {
+                    "[ ]"
+                } else {
+                    "[x]"
+                };
+                ListItem::new(format!("{} {}", checkbox, column))
+            })
+            .collect::<Vec<_>>();
+
+        frame.render_widget(Clear, picker_area);
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .highlight_style(THEME.selection),
+            picker_area,
+            &mut ListState::default().with_selected(Some(self.selected)),
+        );
+    }
+}
@@ -1,21 +1,32 @@
 use std::{
     collections::HashSet,
-    fs::{File, OpenOptions},
+    fs::{self, File, OpenOptions},
     io::{Read, Write},
     process::Command,
     thread,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use crossterm::event;
-use ratatui::{style::Style, widgets::Paragraph};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
 use regex::Regex;
 
 use super::base::{Component, ComponentCreateInfo};
 use crate::{
     managers::event_manager::{ConnectionEvent, Event, EventHandler},
     ui::layouts::CLI_ARGS,
-    utils::{external_editor::HISTORY_FILE, fuzzy::filter_fuzzy_matches},
+    utils::{
+        external_editor::{DEBUG_FILE, HISTORY_FILE, MONGO_COLLECTIONS_FILE},
+        fuzzy::filter_fuzzy_matches,
+        keymap::{Action, KEYMAP},
+        pipeline_file::get_pipeline_query,
+        profiles::{get_profile_names, resolve_connection},
+        theme::THEME,
+    },
 };
 
 #[derive(Default, Clone)]
@@ -37,6 +48,10 @@ pub struct CommandComponent {
     history: Vec<String>,
     history_index: i32,
     history_filtered: Vec<String>,
+    /// Best match for the word being typed, accepted with Tab
+    completion: Option<String>,
+    /// Collection the last pipeline file was run on, used when the aggregate command omits it
+    aggregate_collection: Option<String>,
 }
 
 impl CommandComponent {
@@ -46,31 +61,123 @@ impl CommandComponent {
 
         handle.read_to_string(&mut buffer).unwrap();
 
-        let history: Vec<String> = buffer
+        let commands = buffer
             .split('\n')
-            .collect::<HashSet<&str>>()
-            .into_iter()
-            .filter_map(|s| {
-                if s.is_empty() {
-                    return None;
-                }
-                Some(s.to_string())
-            })
-            .collect();
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let history = get_recent_unique(&commands, HISTORY_LIMIT);
+        if history.len() != commands.len() {
+            // Oldest first, so the new commands can still be appended
+            let content = history
+                .iter()
+                .rev()
+                .map(|command| format!("{}\n", command))
+                .collect::<String>();
+            if let Err(err) = fs::write(HISTORY_FILE.to_string(), content) {
+                DEBUG_FILE.write_log(&err);
+            }
+        }
 
         Self {
             info,
             history_filtered: history.clone(),
             history,
             history_index: 0,
+            completion: None,
+            aggregate_collection: None,
         }
     }
 
+    /// Input starting with `/` is a regex matched anywhere in the commands, it's fuzzy matched
+    /// when the regex is invalid
     fn refresh_history_filtered(&mut self) {
-        self.history_filtered = filter_fuzzy_matches(&self.info.data.value, &self.history);
+        let value = &self.info.data.value;
+        self.history_filtered = match value.strip_prefix('/') {
+            Some(pattern) => match Regex::new(pattern) {
+                Ok(regex) => self
+                    .history
+                    .iter()
+                    .filter(|command| regex.is_match(command))
+                    .cloned()
+                    .collect(),
+                Err(_) => filter_fuzzy_matches(pattern, &self.history),
+            },
+            None => filter_fuzzy_matches(value, &self.history),
+        };
     }
+
+    /// Word being typed and the values it can be completed to, the command name, the profile
+    /// name of the connect command or the collection of the describe and aggregate commands
+    fn get_completion_context(&self) -> Option<(&str, Vec<String>)> {
+        if self.info.data.value.starts_with('/') {
+            return None;
+        }
+        match self.info.data.value.split_once(' ') {
+            None => Some((
+                self.info.data.value.as_str(),
+                COMMANDS.iter().map(|command| command.to_string()).collect(),
+            )),
+            Some(("connect", profile)) if !profile.starts_with('!') => {
+                Some((profile, get_profile_names()))
+            }
+            Some(("describe", collection)) => Some((collection, get_collection_names())),
+            // Collection follows the path of the pipeline file
+            Some(("aggregate", args)) => match args.split_once(' ') {
+                Some((_, collection)) if !collection.contains(' ') => {
+                    Some((collection, get_collection_names()))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn refresh_completion(&mut self) {
+        self.completion = self
+            .get_completion_context()
+            .filter(|(word, _)| !word.is_empty())
+            .and_then(|(word, values)| {
+                let matches = filter_fuzzy_matches(word, &values);
+                // Prefix matches can be shown as the rest of the word
+                matches
+                    .iter()
+                    .find(|value| value.starts_with(word))
+                    .or(matches.first())
+                    .filter(|value| value.as_str() != word)
+                    .cloned()
+            });
+    }
+
+    fn accept_completion(&mut self) {
+        let Some(completion) = self.completion.take() else {
+            return;
+        };
+        let word_len = self
+            .get_completion_context()
+            .map(|(word, _)| word.len())
+            .unwrap_or_default();
+        let value = &self.info.data.value;
+        let prefix = &value[..value.len() - word_len];
+        // Command name is followed by its argument
+        self.info.data.value = if prefix.is_empty() {
+            format!("{} ", completion)
+        } else {
+            format!("{}{}", prefix, completion)
+        };
+        self.refresh_completion();
+    }
+}
+
+/// Collections of the current connection, as they were listed last
+fn get_collection_names() -> Vec<String> {
+    fs::read_to_string(MONGO_COLLECTIONS_FILE.as_str())
+        .map(|content| content.lines().map(String::from).collect())
+        .unwrap_or_default()
 }
 
+/// Commands that can be entered in the command line
+const COMMANDS: [&str; 5] = ["use", "connect", "aggregate", "describe", "refresh"];
+
 impl Component for CommandComponent {
     fn get_constraint(&self) -> ratatui::prelude::Constraint {
         self.info.constraint
@@ -85,11 +192,16 @@ impl Component for CommandComponent {
         visible
     }
 
+    fn is_focusable(&self) -> bool {
+        self.info.focusable
+    }
+
     fn draw(&mut self, info: super::base::ComponentDrawInfo) {
-        let mut style = Style::default();
-        if matches!(self.info.data.severity, Severity::Error) {
-            style = style.fg(ratatui::style::Color::Red);
-        }
+        let style = match self.info.data.severity {
+            Severity::Normal => THEME.normal_message,
+            Severity::Info => THEME.info_message,
+            Severity::Error => THEME.error_message,
+        };
 
         info.frame.render_widget(
             Paragraph::new(self.get_text_to_render()).style(style),
@@ -99,15 +211,46 @@ impl Component for CommandComponent {
 }
 
 impl CommandComponent {
-    fn get_text_to_render(&self) -> String {
-        if self.info.is_focused {
-            return format!(":{}█", self.info.data.value);
+    fn get_text_to_render(&self) -> Line<'_> {
+        if !self.info.is_focused {
+            return Line::from(self.info.data.value.clone());
         }
 
-        self.info.data.value.clone()
+        let mut spans = vec![Span::raw(format!(":{}█", self.info.data.value))];
+        if let Some(completion) = &self.completion {
+            let word = self
+                .get_completion_context()
+                .map(|(word, _)| word)
+                .unwrap_or_default();
+            let shadow_text = match completion.strip_prefix(word) {
+                Some(rest) => rest.to_string(),
+                None => format!(" {}", completion),
+            };
+            spans.push(Span::styled(
+                shadow_text,
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+
+        Line::from(spans)
     }
 }
 
+/// Commands kept in the history file, older ones are dropped on startup
+const HISTORY_LIMIT: usize = 1000;
+
+/// Last `limit` unique commands, most recent first
+fn get_recent_unique(commands: &[&str], limit: usize) -> Vec<String> {
+    let mut seen = HashSet::new();
+    commands
+        .iter()
+        .rev()
+        .filter(|command| seen.insert(**command))
+        .take(limit)
+        .map(|command| command.to_string())
+        .collect()
+}
+
 // Not bug proof
 const COMMAND_REGEX: &str = r#"^([^ ]*) ((!\((.*)\))|(.*))"#;
 
@@ -117,7 +260,7 @@ impl EventHandler for CommandComponent {
             Event::OnMessage(value) => self.info.data = value.clone(),
             Event::OnInput(value) => match value.mode {
                 crate::application::Mode::View => {
-                    if let event::KeyCode::Char(':') = value.key.code {
+                    if KEYMAP.is_action(value.key.code, Action::OpenCommandLine) {
                         self.info.is_focused = true;
                         self.info.data = Message::default();
                         self.history_index = 0;
@@ -128,15 +271,19 @@ impl EventHandler for CommandComponent {
                         self.info.data = Message::default();
                         self.history_index = 0;
                         self.info.is_focused = false;
+                        self.completion = None;
                     }
                     event::KeyCode::Char(value) => {
                         self.info.data.value += &value.to_string();
                         self.history_index = -1;
+                        self.refresh_completion();
                     }
                     event::KeyCode::Backspace => {
                         self.info.data.value.pop();
                         self.history_index = -1;
+                        self.refresh_completion();
                     }
+                    event::KeyCode::Tab => self.accept_completion(),
                     event::KeyCode::Up => {
                         if self.history_index == -1 {
                             self.refresh_history_filtered();
@@ -168,6 +315,7 @@ impl EventHandler for CommandComponent {
                     }
                     event::KeyCode::Enter => {
                         self.info.is_focused = false;
+                        self.completion = None;
                         self.history_index = -1;
                         let (command, arg0) = Regex::new(COMMAND_REGEX)?
                             .captures(&self.info.data.value)
@@ -224,7 +372,38 @@ impl EventHandler for CommandComponent {
                             }
                             "connect" => {
                                 self.info.event_sender.send(Event::OnConnection(
-                                    ConnectionEvent::Connect(arg0.to_string()),
+                                    ConnectionEvent::Connect(resolve_connection(&arg0)?),
+                                ))?;
+                                self.info.data.value = String::new();
+                            }
+                            "aggregate" => {
+                                let mut args = arg0.split_whitespace();
+                                let path = args.next().with_context(|| "Path is missing")?;
+                                let collection = args
+                                    .next()
+                                    .map(String::from)
+                                    .or_else(|| self.aggregate_collection.clone())
+                                    .with_context(|| {
+                                        "Collection is missing, use: aggregate <path> <collection>"
+                                    })?;
+
+                                let query = get_pipeline_query(path, &collection)?;
+                                self.aggregate_collection = Some(collection);
+                                self.info.event_sender.send(Event::OnQuery(query))?;
+                                self.info.data.value = String::new();
+                            }
+                            "describe" => {
+                                self.info
+                                    .event_sender
+                                    .send(Event::OnDescribe(arg0.trim().to_string()))?;
+                                self.info.data.value = String::new();
+                            }
+                            "refresh" => {
+                                if arg0.trim() != "collections" {
+                                    bail!("Nothing to refresh, use: refresh collections");
+                                }
+                                self.info.event_sender.send(Event::OnConnection(
+                                    ConnectionEvent::RefreshCollections,
                                 ))?;
                                 self.info.data.value = String::new();
                             }
@@ -244,3 +423,19 @@ impl EventHandler for CommandComponent {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_last_unique_commands() {
+        let commands = ["use a", "use b", "use a", "use c", "use b"];
+
+        assert_eq!(get_recent_unique(&commands, 2), vec!["use b", "use c"]);
+        assert_eq!(
+            get_recent_unique(&commands, 10),
+            vec!["use b", "use c", "use a"]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::{cmp, collections::VecDeque};
+
+use chrono::{DateTime, Local};
+use crossterm::event;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+use super::command::{Message, Severity};
+use crate::utils::theme::THEME;
+
+/// Messages kept in the log, the oldest ones are dropped
+const MESSAGE_LOG_LIMIT: usize = 200;
+
+/// Messages shown in the command line during the session, as they replace each other there
+#[derive(Default)]
+pub struct MessageLog {
+    messages: VecDeque<(DateTime<Local>, Message)>,
+    /// Selected message while the log is open, `None` while it's closed
+    selected: Option<usize>,
+}
+
+impl MessageLog {
+    pub fn push(&mut self, message: &Message) {
+        // Empty message only clears the command line
+        if message.value.is_empty() {
+            return;
+        }
+        if self.messages.len() == MESSAGE_LOG_LIMIT {
+            self.messages.pop_front();
+            self.selected = self.selected.map(|selected| selected.saturating_sub(1));
+        }
+        self.messages.push_back((Local::now(), message.clone()));
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.selected.is_some()
+    }
+
+    /// Opened with the newest message selected
+    pub fn open(&mut self) {
+        self.selected = Some(self.messages.len().saturating_sub(1));
+    }
+
+    /// Moved with j/k or arrows, closed with Esc, q or m
+    pub fn on_key(&mut self, key: event::KeyCode) {
+        let Some(selected) = self.selected else {
+            return;
+        };
+        self.selected = match key {
+            event::KeyCode::Esc | event::KeyCode::Char('q') | event::KeyCode::Char('m') => None,
+            event::KeyCode::Down | event::KeyCode::Char('j') => Some(cmp::min(
+                selected + 1,
+                self.messages.len().saturating_sub(1),
+            )),
+            event::KeyCode::Up | event::KeyCode::Char('k') => Some(selected.saturating_sub(1)),
+            event::KeyCode::Char('g') => Some(0),
+            event::KeyCode::Char('G') => Some(self.messages.len().saturating_sub(1)),
+            _ => Some(selected),
+        };
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect) {
+        let width = cmp::max(area.width * 4 / 5, cmp::min(area.width, 40));
+        let height = cmp::max(area.height * 4 / 5, cmp::min(area.height, 5));
+        let log_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let items = self
+            .messages
+            .iter()
+            .map(|(time, message)| {
+                let style = match message.severity {
+                    Severity::Normal => THEME.normal_message,
+                    Severity::Info => THEME.info_message,
+                    Severity::Error => THEME.error_message,
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} ", time.format("%H:%M:%S"))),
+                    Span::styled(message.value.clone(), style),
+                ]))
+            })
+            .collect::<Vec<_>>();
+
+        frame.render_widget(Clear, log_area);
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!(" Messages ({}) ", self.messages.len())),
+                )
+                .highlight_style(THEME.selection),
+            log_area,
+            &mut ListState::default().with_selected(self.selected),
+        );
+    }
+}
@@ -1,6 +1,10 @@
 pub mod base;
+pub mod column_jump;
+pub mod column_picker;
 pub mod command;
 pub mod input;
+pub mod message_log;
 pub mod paragraph;
 pub mod scrollable_table;
 pub mod status_line;
+pub mod tabs;
@@ -1,44 +1,132 @@
-use std::{cmp, collections::HashSet, fs::File, io::Read, sync::Arc, time::SystemTime};
+use std::{
+    cmp,
+    collections::HashSet,
+    fs,
+    sync::{mpsc::Sender, Arc},
+    time::SystemTime,
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use arboard::Clipboard;
+use chrono::{DateTime, Utc};
 use crossterm::event;
-use ratatui::layout::Constraint;
-use rusty_db_cli_mongo::interpreter::InterpreterError;
-use tokio::sync::Mutex;
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, Paragraph},
+};
+use rusty_db_cli_mongo::{
+    interpreter::InterpreterError,
+    lexer::{Lexer, Token, TokenType},
+    types::literals::{Literal, Number},
+};
+use tokio::{
+    sync::Mutex,
+    task::{AbortHandle, JoinHandle},
+};
 
 use super::{
     base::{Component, ComponentCreateInfo, ComponentDrawInfo},
+    column_jump::{ColumnJump, ColumnJumpAction},
+    column_picker::{ColumnPicker, ColumnPickerAction},
     command::{Message, Severity},
 };
 use crate::{
     connectors::base::{
-        Connector, DatabaseData, DatabaseFetchResult, Object, PaginationInfo, TableData, LIMIT,
+        get_page_size, retry, Connector, ConnectorInfo, DatabaseData, DatabaseFetchResult,
+        DatabaseValue, Object, PaginationInfo, TableData,
     },
     log_error,
     managers::event_manager::{ConnectionEvent, Event, EventHandler},
     try_from,
     types::{HorizontalDirection, VerticalDirection},
-    utils::external_editor::{FileType, DEBUG_FILE, EXTERNAL_EDITOR, MONGO_QUERY_FILE},
+    ui::layouts::CLI_ARGS,
+    utils::{
+        canonical_query::get_canonical_query,
+        document_history::{DocumentHistory, OpenedDocument},
+        external_editor::{
+            get_query_file, write_file_atomically, FileType, DEBUG_FILE, EXTERNAL_EDITOR,
+        },
+        keymap::{Action, KEYMAP},
+        projection::set_find_projection,
+        query_history::QueryHistory,
+        theme::THEME,
+    },
     widgets::{
         scrollable_table::{Row, ScrollableTable, ScrollableTableState},
         throbber::{get_throbber_data, Throbber, ThrobberState},
     },
 };
 
+/// Rows moved by one step of the mouse wheel
+const MOUSE_SCROLL_ROWS: usize = 3;
+
+/// Documents opened with E that are kept for the session
+const OPENED_DOCUMENTS_LIMIT: usize = 50;
+
 pub struct ScrollableTableComponent {
     info: ComponentCreateInfo<TableData<'static>>,
     data: DatabaseData,
     is_fetching: bool,
+    /// Part of the result of the running query is already shown
+    has_partial_data: bool,
+    /// Any query has finished, tells an empty result apart from the state before the first query
+    has_query_result: bool,
+    /// Last query failed, the rows of the previous result are kept
+    query_error: Option<String>,
+    /// Running query, with the time it was started at to recognize its results
+    query_handle: Option<(JoinHandle<()>, SystemTime)>,
+    /// Every query also counts all of its matching documents, toggled as it doubles the load
+    count_documents: bool,
+    count_handle: Option<AbortHandle>,
+    /// Documents shown in the detail pane or opened with E keep the types of their values, e.g.
+    /// `{"$oid": "..."}`
+    open_extended_json: bool,
+    /// Dates are shown as the time since the rows were built, e.g. `3 days ago`
+    relative_dates: bool,
+    /// Shows the selected document beside the table
+    show_detail: bool,
+    detail_scroll: u16,
+    /// Column of joined documents, e.g. of `$lookup`, shown in the detail pane instead of the
+    /// whole document
+    detail_column: Option<String>,
+    /// Document the detail pane was scrolled for, the scroll is reset when another one is selected
+    detail_index: usize,
+    /// All columns of the fetched documents, including the hidden ones
+    columns: Vec<String>,
+    /// Kept while the same query is used, e.g. between its pages
+    hidden_columns: HashSet<String>,
+    column_picker: Option<ColumnPicker>,
+    column_jump: Option<ColumnJump>,
+    /// Where the table was drawn last, empty while it's not shown. Mouse events are matched to
+    /// its rows and columns
+    table_area: Rect,
+    /// Width of the table the cell widths were calculated for, they are recalculated when it's
+    /// drawn with another width, after the terminal is resized or the detail pane is toggled
+    cell_widths_width: u16,
+    /// Documents opened with E, as they were before editing
+    opened_documents: DocumentHistory,
     state: ScrollableTableState,
     query: String,
+    /// File where the query of the current connection is stored
+    query_file: String,
+    query_history: Option<QueryHistory>,
+    /// How many queries back in the history the current query is, `None` when it's not from it
+    query_history_age: Option<usize>,
     connector: Arc<Mutex<dyn Connector>>,
     horizontal_offset: i32,
     vertical_offset: i32,
     horizontal_offset_max: i32,
     vertical_offset_max: i32,
+    /// First key of a two key sequence, like `gg`
+    pending_key: Option<char>,
+    /// Kept alive, on some platforms the copied value disappears with it
+    clipboard: Option<Clipboard>,
     pagination: PaginationInfo,
     loader_state: ThrobberState,
     loader_steps: Vec<String>,
+    /// Shown next to the throbber, names the running command
+    loader_label: String,
 }
 
 impl ScrollableTableComponent {
@@ -46,19 +134,40 @@ impl ScrollableTableComponent {
         info: ComponentCreateInfo<TableData<'static>>,
         state: ScrollableTableState,
         conn: Arc<Mutex<dyn Connector>>,
+        connection: &ConnectorInfo,
     ) -> Self {
-        let mut handle =
-            File::open(MONGO_QUERY_FILE.to_string()).expect("Failed to read query file");
-        let mut query = String::new();
-        handle
-            .read_to_string(&mut query)
-            .expect("Failed to read query file");
+        let query_file = get_query_file(&connection.host, &connection.database);
+        let query = fs::read_to_string(&query_file).expect("Failed to read query file");
+        let query_history = QueryHistory::load(&connection.host, &connection.database);
+        log_error!(info.event_sender, query_history.as_ref().err());
 
         let (throbber_steps, throbber_state) = get_throbber_data();
 
         Self {
             is_fetching: false,
+            has_partial_data: false,
+            has_query_result: false,
+            query_error: None,
+            query_handle: None,
+            count_documents: false,
+            count_handle: None,
+            open_extended_json: false,
+            relative_dates: false,
+            show_detail: false,
+            detail_scroll: 0,
+            detail_column: None,
+            detail_index: 0,
+            columns: Vec::new(),
+            hidden_columns: HashSet::new(),
+            column_picker: None,
+            column_jump: None,
+            table_area: Rect::default(),
+            cell_widths_width: 0,
+            opened_documents: DocumentHistory::new(OPENED_DOCUMENTS_LIMIT),
             query,
+            query_file,
+            query_history: query_history.ok(),
+            query_history_age: None,
             data: DatabaseData(Vec::new()),
             info,
             state,
@@ -67,12 +176,15 @@ impl ScrollableTableComponent {
             vertical_offset: 0,
             horizontal_offset_max: 0,
             vertical_offset_max: 0,
+            pending_key: None,
+            clipboard: None,
             pagination: PaginationInfo {
                 start: 0,
-                limit: LIMIT,
+                limit: get_page_size(),
             },
             loader_state: throbber_state,
             loader_steps: throbber_steps,
+            loader_label: get_loader_label(""),
         }
     }
 
@@ -82,6 +194,63 @@ impl ScrollableTableComponent {
         self.vertical_offset = 0;
     }
 
+    fn load_query(&mut self, host: &str, database: &str) {
+        self.query_file = get_query_file(host, database);
+        match fs::read_to_string(&self.query_file) {
+            Ok(query) => self.query = query,
+            Err(err) => log_error!(self.info.event_sender, Some(err)),
+        }
+
+        self.hidden_columns.clear();
+
+        let query_history = QueryHistory::load(host, database);
+        log_error!(self.info.event_sender, query_history.as_ref().err());
+        self.query_history = query_history.ok();
+        self.query_history_age = None;
+    }
+
+    /// Replaces the query with the one from history and runs it, `older` moves back in history
+    fn cycle_query_history(&mut self, older: bool) -> Result<()> {
+        let Some(history) = &self.query_history else {
+            return Ok(());
+        };
+
+        let age = match (self.query_history_age, older) {
+            // Current query is usually the newest one, which would make the first step a no-op
+            (None, true) if history.get(0) == Some(&self.query) => Some(1),
+            (None, true) => Some(0),
+            (Some(age), true) => Some(age + 1),
+            (Some(age), false) => age.checked_sub(1),
+            (None, false) => None,
+        };
+
+        let Some((age, query)) = age.and_then(|age| Some((age, history.get(age)?.clone()))) else {
+            let direction = if older { "older" } else { "newer" };
+            self.info.event_sender.send(Event::OnMessage(Message {
+                value: format!("No {} query in history", direction),
+                severity: Severity::Info,
+            }))?;
+            return Ok(());
+        };
+
+        self.info.event_sender.send(Event::OnMessage(Message {
+            value: format!(
+                "Query {}/{} from history",
+                history.len() - age,
+                history.len()
+            ),
+            severity: Severity::Info,
+        }))?;
+        self.query = query;
+        self.hidden_columns.clear();
+        self.query_history_age = Some(age);
+        write_file_atomically(&self.query_file, &self.query)?;
+
+        self.run_query();
+
+        Ok(())
+    }
+
     pub fn set_connector(&mut self, conn: Arc<Mutex<dyn Connector>>) {
         // TODO: This is ugly, the get_table_layout fn should instead accept builder struct
         self.connector = conn;
@@ -102,6 +271,76 @@ impl ScrollableTableComponent {
             .set_horizontal_offset(self.horizontal_offset as usize);
     }
 
+    /// Collection of the query, shown as the name of its tab
+    pub fn get_title(&self) -> String {
+        get_collection_name(&self.query).unwrap_or_else(|| String::from("query"))
+    }
+
+    /// Stores the new query in the query file and the history, then runs it
+    fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.hidden_columns.clear();
+        log_error!(
+            self.info.event_sender,
+            write_file_atomically(&self.query_file, &self.query).err()
+        );
+        if let Some(history) = &mut self.query_history {
+            log_error!(self.info.event_sender, history.push(&self.query).err());
+        }
+        self.query_history_age = None;
+        self.run_query();
+    }
+
+    fn get_table(&self) -> ScrollableTable<'static> {
+        ScrollableTable::new(self.info.data.rows.clone(), self.info.data.header.clone())
+            .header_style(THEME.header)
+            .highlight_style(THEME.selection)
+    }
+
+    /// Runs the query from its first page
+    fn run_query(&mut self) {
+        self.reset_state();
+        self.pagination.reset();
+        self.spawn_next_data();
+        self.spawn_document_count();
+    }
+
+    /// Counts documents matching the query in the background when enabled, the previous count
+    /// is cleared either way as it belongs to another query
+    fn spawn_document_count(&mut self) {
+        if let Some(handle) = self.count_handle.take() {
+            handle.abort();
+        }
+        self.info
+            .event_sender
+            .send(Event::OnDocumentCount(None))
+            .unwrap();
+        if !self.count_documents {
+            return;
+        }
+
+        let (connector, query, event_sender) = (
+            self.connector.clone(),
+            self.query.clone(),
+            self.info.event_sender.clone(),
+        );
+        let handle = tokio::spawn(async move {
+            let result = connector.lock().await.count_documents(query).await;
+            match result {
+                Ok(Some(count)) => event_sender
+                    .send(Event::OnDocumentCount(Some(count)))
+                    .unwrap(),
+                Ok(None) => (),
+                Err(err) => log_error!(event_sender, Some(err)),
+            }
+        });
+        self.count_handle = Some(handle.abort_handle());
+        self.info
+            .event_sender
+            .send(Event::OnAsyncEvent(handle))
+            .unwrap();
+    }
+
     pub fn spawn_next_data(&mut self) {
         let (cloned_conn, cloned_query, cloned_pagination, event_sender) = (
             self.connector.clone(),
@@ -109,37 +348,249 @@ impl ScrollableTableComponent {
             self.pagination,
             self.info.event_sender.clone(),
         );
+        self.cancel_query();
+        self.loader_label = get_loader_label(&self.query);
         self.is_fetching = true;
-        tokio::spawn(async move {
-            let fetch_start = SystemTime::now();
-            let result = cloned_conn
-                .lock()
-                .await
-                .get_data(cloned_query, cloned_pagination)
-                .await;
-            match result {
-                Ok(data) => {
-                    event_sender
-                        .send(Event::DatabaseData(DatabaseFetchResult {
-                            data,
-                            fetch_start,
-                            trigger_query_took_message: true,
-                        }))
-                        .unwrap();
-                }
-                Err(err) => {
-                    DEBUG_FILE.write_log(&err);
-                    event_sender
-                        .send(Event::DatabaseData(DatabaseFetchResult {
-                            data: DatabaseData(Vec::new()),
-                            fetch_start,
-                            trigger_query_took_message: false,
-                        }))
-                        .unwrap();
-                    log_error!(event_sender, Some(err));
-                }
-            };
+        self.has_partial_data = false;
+        let fetch_start = SystemTime::now();
+        let handle = tokio::spawn(async move {
+            let (conn, query, sender) = (&cloned_conn, &cloned_query, &event_sender);
+            let result = retry(
+                || async move {
+                    let batch_sender = sender.clone();
+                    let on_batch = Box::new(move |data| {
+                        batch_sender
+                            .send(Event::DatabaseData(DatabaseFetchResult {
+                                data,
+                                fetch_start,
+                                trigger_query_took_message: false,
+                                is_partial: true,
+                                error: None,
+                            }))
+                            .unwrap();
+                    });
+                    conn.lock()
+                        .await
+                        .get_data(query.clone(), cloned_pagination, on_batch)
+                        .await
+                },
+                |attempt, err| send_retry_message(&event_sender, attempt, err),
+            )
+            .await;
+            send_fetch_result(&event_sender, fetch_start, result);
         });
+        self.query_handle = Some((handle, fetch_start));
+    }
+
+    /// Shows fields of the collection in place of the query result, the query itself is kept
+    fn spawn_describe(&mut self, name: String) {
+        let (cloned_conn, event_sender) = (self.connector.clone(), self.info.event_sender.clone());
+        self.cancel_query();
+        self.reset_state();
+        self.loader_label = format!("Describing {}...", name);
+        self.is_fetching = true;
+        self.has_partial_data = false;
+        let fetch_start = SystemTime::now();
+        let handle = tokio::spawn(async move {
+            let (conn, name) = (&cloned_conn, &name);
+            let result = retry(
+                || async move { conn.lock().await.describe(name).await },
+                |attempt, err| send_retry_message(&event_sender, attempt, err),
+            )
+            .await;
+            send_fetch_result(&event_sender, fetch_start, result);
+        });
+        self.query_handle = Some((handle, fetch_start));
+    }
+
+    /// Aborts the running query, returns whether there was any
+    fn cancel_query(&mut self) -> bool {
+        match &self.query_handle {
+            Some((handle, _)) if !handle.is_finished() => {
+                handle.abort();
+                self.query_handle = None;
+                self.is_fetching = false;
+                self.has_partial_data = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Aborts the running query and count, so they don't report into a table that's gone
+    pub fn close(&mut self) {
+        self.cancel_query();
+        if let Some(handle) = self.count_handle.take() {
+            handle.abort();
+        }
+    }
+
+    fn get_selected_index(&self) -> usize {
+        self.state.get_vertical_select() - 1 + self.state.get_vertical_offset()
+    }
+
+    fn get_selected_document(&self) -> Option<Object> {
+        self.data.get(self.get_selected_index()).cloned()
+    }
+
+    fn get_document_json(&self, document: Object) -> serde_json::Value {
+        if self.open_extended_json {
+            document.into_extended_json()
+        } else {
+            document.into()
+        }
+    }
+
+    /// Pretty JSON of the selected document, scrolled independently of the table
+    fn get_detail_pane(&mut self) -> Paragraph<'static> {
+        // Joined documents belong to the cell they were opened from, other cells show the document
+        let selected_index = self.get_selected_index();
+        if self.detail_index != selected_index {
+            self.detail_index = selected_index;
+            self.detail_scroll = 0;
+            self.detail_column = None;
+        }
+        if self.detail_column.is_some() && self.detail_column != self.get_selected_column() {
+            self.detail_column = None;
+            self.detail_scroll = 0;
+        }
+
+        let content = self
+            .get_selected_document()
+            .map(|mut document| {
+                match self
+                    .detail_column
+                    .as_ref()
+                    .and_then(|column| document.remove(column))
+                {
+                    Some(value) if self.open_extended_json => value.into_extended_json(),
+                    Some(value) => value.into(),
+                    None => self.get_document_json(document),
+                }
+            })
+            .and_then(|json| serde_json::to_string_pretty(&json).ok())
+            .unwrap_or_default();
+        let line_count = content.lines().count() as u16;
+        self.detail_scroll = cmp::min(self.detail_scroll, line_count.saturating_sub(1));
+
+        Paragraph::new(content)
+            .block(
+                Block::default()
+                    .borders(Borders::LEFT)
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            )
+            .scroll((self.detail_scroll, 0))
+    }
+
+    /// Opens the detail pane, with the joined documents when they are in the selected cell
+    fn toggle_detail(&mut self) {
+        self.show_detail = !self.show_detail;
+        self.detail_column = None;
+        if !self.show_detail {
+            return;
+        }
+
+        let column = self.get_selected_column();
+        let document = self.data.get(self.get_selected_index());
+        if let (Some(column), Some(document)) = (column, document) {
+            if document.get(&column).is_some_and(is_document_array) {
+                self.detail_column = Some(column);
+                self.detail_index = self.get_selected_index();
+                self.detail_scroll = 0;
+            }
+        }
+    }
+
+    /// First visible column, the one the horizontal scroll stopped at
+    fn get_selected_column(&self) -> Option<String> {
+        let cell = self
+            .info
+            .data
+            .header
+            .cells
+            .get(self.horizontal_offset as usize)?;
+
+        Some(
+            cell.content
+                .lines
+                .iter()
+                .flat_map(|line| line.spans.iter())
+                .map(|span| span.content.as_ref())
+                .collect::<String>(),
+        )
+    }
+
+    /// Copies value of the first visible column in the selected row, returns name of the column
+    fn copy_selected_cell(&mut self) -> Result<String> {
+        let row = self
+            .data
+            .get(self.get_selected_index())
+            .context("No row is selected")?;
+        let key = self
+            .get_selected_column()
+            .context("No column is selected")?;
+
+        let value = match row.get(&key) {
+            Some(DatabaseValue::String(str)) => str.clone(),
+            Some(DatabaseValue::ObjectId(object_id)) => object_id.to_hex(),
+            Some(value) => value.clone().into_extended_json().to_string(),
+            None => String::new(),
+        };
+
+        self.set_clipboard(value)?;
+
+        Ok(key)
+    }
+
+    fn set_clipboard(&mut self, value: String) -> Result<()> {
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard,
+            None => self.clipboard.insert(Clipboard::new()?),
+        };
+        clipboard.set_text(value)?;
+
+        Ok(())
+    }
+
+    fn set_horizontal_position(&mut self, position: i32) {
+        self.horizontal_offset = cmp::max(cmp::min(position, self.horizontal_offset_max), 0);
+        self.state
+            .set_horizontal_offset(self.horizontal_offset as usize);
+    }
+
+    fn set_vertical_position(&mut self, position: i32) {
+        self.vertical_offset = cmp::max(cmp::min(position, self.vertical_offset_max), 1);
+
+        if self.vertical_offset > 10 {
+            self.state
+                .set_vertical_offset((self.vertical_offset - 10) as usize);
+            self.state.set_vertical_select(10);
+        } else {
+            self.state.set_vertical_offset(0);
+            self.state
+                .set_vertical_select(self.vertical_offset as usize);
+        }
+    }
+
+    fn jump_to_top(&mut self) {
+        if self.pagination.start > 0 {
+            self.reset_state();
+            self.pagination.start = 0;
+            self.state
+                .set_horizontal_offset(self.horizontal_offset as usize);
+            self.spawn_next_data();
+        }
+        self.set_vertical_position(1);
+    }
+
+    fn jump_to_bottom(&mut self) {
+        if self.vertical_offset_max < 2 {
+            self.set_vertical_position(1);
+            return;
+        }
+        // Moving onto the last row goes to the next page, when the current one is full
+        self.set_vertical_position(self.vertical_offset_max - 1);
+        self.handle_next_vertical_movement(VerticalDirection::Down);
     }
 
     pub fn handle_next_vertical_movement(&mut self, dir: VerticalDirection) {
@@ -166,10 +617,11 @@ impl ScrollableTableComponent {
             self.state
                 .set_vertical_select(self.vertical_offset as usize);
         }
+        let page_size = get_page_size();
         let offset = self.state.get_vertical_offset() + self.state.get_vertical_select();
-        if offset == LIMIT as usize && matches!(dir, VerticalDirection::Down) {
+        if offset == page_size as usize && matches!(dir, VerticalDirection::Down) {
             self.vertical_offset = 1;
-            self.pagination.start += (LIMIT - 1) as u64;
+            self.pagination.start += (page_size - 1) as u64;
             self.state.reset();
             self.state
                 .set_horizontal_offset(self.horizontal_offset as usize);
@@ -178,24 +630,25 @@ impl ScrollableTableComponent {
         if offset == 1
             && matches!(dir, VerticalDirection::Up)
             && self.pagination.start > 0
-            && (self.pagination.start % (LIMIT - 1) as u64).to_string() == "0"
+            && (self.pagination.start % (page_size - 1) as u64).to_string() == "0"
         {
-            self.vertical_offset = (LIMIT - 1) as i32;
+            self.vertical_offset = (page_size - 1) as i32;
+            // Pages smaller than the scroll margin don't scroll at all
+            let select = cmp::min(self.vertical_offset, 10);
             self.state
-                .set_vertical_offset((self.vertical_offset - 10) as usize);
-            self.state.set_vertical_select(10);
-            self.pagination.start -= (LIMIT - 1) as u64;
+                .set_vertical_offset((self.vertical_offset - select) as usize);
+            self.state.set_vertical_select(select as usize);
+            self.pagination.start -= (page_size - 1) as u64;
             self.spawn_next_data();
         }
     }
 
     fn set_data(&mut self, result: DatabaseFetchResult) -> anyhow::Result<()> {
+        self.has_query_result = true;
+        self.query_error = None;
         self.data = result.data;
-        self.info.data = TableData::from(self.data.clone());
-        self.horizontal_offset_max = self.info.data.header.cells.len() as i32 - 1;
-        self.vertical_offset_max = self.info.data.rows.len() as i32;
-        // TODO: We should keep order of the fields between refteches
-        self.calculate_cell_widths();
+        self.columns = get_columns(&self.data);
+        self.refresh_table_data();
 
         if result.trigger_query_took_message {
             let cloned_sender = self.info.event_sender.clone();
@@ -220,44 +673,129 @@ impl ScrollableTableComponent {
         Ok(())
     }
 
-    fn calculate_cell_widths(&mut self) {
-        self.state.cell_widths = self
-            .info
-            .data
-            .header
-            .cells
+    /// Rebuilds the rows from the fetched documents, without the hidden columns
+    fn refresh_table_data(&mut self) {
+        let format = CellFormat {
+            thousands_separator: CLI_ARGS.thousands_separator,
+            relative_to: self.relative_dates.then(Utc::now),
+        };
+        self.info.data = get_table_data(self.data.clone(), &self.get_visible_columns(), format);
+        self.horizontal_offset_max = self.info.data.header.cells.len() as i32 - 1;
+        self.vertical_offset_max = self.info.data.rows.len() as i32;
+        // TODO: We should keep order of the fields between refteches
+        self.calculate_cell_widths();
+        self.set_horizontal_position(self.horizontal_offset);
+    }
+
+    fn get_visible_columns(&self) -> Vec<String> {
+        self.columns
             .iter()
-            .enumerate()
-            .map(|(idx, cell)| {
-                if self.info.data.header.cells.len() - 1 == idx {
-                    // Last cell should take rest of the remaining space
-                    return u16::MAX;
+            .filter(|column| !self.hidden_columns.contains(*column))
+            .cloned()
+            .collect()
+    }
+
+    /// Wheel scrolls the rows, like moving with the keys it goes over to the next page. Click
+    /// selects the row and makes the column the first visible one
+    fn on_mouse(&mut self, mouse: event::MouseEvent) {
+        let area = self.table_area;
+        let is_inside = mouse.column >= area.x
+            && mouse.column < area.right()
+            && mouse.row >= area.y
+            && mouse.row < area.bottom();
+        if !is_inside || self.is_capturing_input() {
+            return;
+        }
+
+        match mouse.kind {
+            event::MouseEventKind::ScrollDown | event::MouseEventKind::ScrollUp => {
+                let dir = match mouse.kind {
+                    event::MouseEventKind::ScrollDown => VerticalDirection::Down,
+                    _ => VerticalDirection::Up,
+                };
+                for _ in 0..MOUSE_SCROLL_ROWS {
+                    // Rows of the next page are not fetched yet
+                    if self.is_fetching {
+                        break;
+                    }
+                    self.handle_next_vertical_movement(dir);
                 }
-                let value_widths = self
-                    .info
+            }
+            event::MouseEventKind::Down(event::MouseButton::Left) => {
+                // First row is the header
+                let row = (mouse.row - area.y) as usize;
+                let rows_shown = self
                     .data
-                    .rows
-                    .iter()
-                    .map(|r| r.cells[idx].content.width() as u16)
-                    .collect::<Vec<_>>();
-
-                let mut cell_width: u16 = 0;
-                let mut size = 0;
-                for width in value_widths.iter() {
-                    if width >= &100 {
-                        continue;
-                    }
-                    if let Some(value) = cell_width.checked_add(*width) {
-                        cell_width = value;
-                        size += 1;
-                    }
+                    .len()
+                    .saturating_sub(self.state.get_vertical_offset());
+                if row > 0 && row <= rows_shown {
+                    self.set_vertical_position((self.state.get_vertical_offset() + row) as i32);
+                }
+                if let Some(column) = self.get_column_at(mouse.column - area.x) {
+                    self.set_horizontal_position(column as i32);
                 }
-                let cell_avg_width = cell_width.checked_div(size).unwrap_or(0);
-                let header_cell_width = cmp::min(cell.content.width(), 30) as u16;
+            }
+            _ => {}
+        }
+    }
 
-                cmp::max(header_cell_width, cell_avg_width)
-            })
-            .collect::<Vec<_>>();
+    /// Column drawn at the given distance from the left edge of the table
+    fn get_column_at(&self, x: u16) -> Option<usize> {
+        let mut column_end: u16 = 0;
+        for (idx, width) in self
+            .state
+            .cell_widths
+            .iter()
+            .enumerate()
+            .skip(self.horizontal_offset as usize)
+        {
+            // Cells are separated by one space
+            column_end = column_end.saturating_add(width.saturating_add(1));
+            if x < column_end {
+                return Some(idx);
+            }
+        }
+
+        None
+    }
+
+    fn on_column_picker_key(&mut self, key: event::KeyCode) {
+        let Some(picker) = &mut self.column_picker else {
+            return;
+        };
+
+        match picker.on_key(key, &self.columns) {
+            ColumnPickerAction::Toggle(column) => {
+                if !self.hidden_columns.remove(&column) {
+                    self.hidden_columns.insert(column);
+                }
+                self.refresh_table_data();
+            }
+            ColumnPickerAction::Close => self.column_picker = None,
+            ColumnPickerAction::None => {}
+        }
+    }
+
+    fn on_column_jump_key(&mut self, key: event::KeyCode) {
+        let columns = self.get_visible_columns();
+        let Some(jump) = &mut self.column_jump else {
+            return;
+        };
+
+        match jump.on_key(key, &columns) {
+            ColumnJumpAction::Jump(column) => {
+                self.column_jump = None;
+                if let Some(idx) = columns.iter().position(|visible| *visible == column) {
+                    self.set_horizontal_position(idx as i32);
+                }
+            }
+            ColumnJumpAction::Close => self.column_jump = None,
+            ColumnJumpAction::None => {}
+        }
+    }
+
+    fn calculate_cell_widths(&mut self) {
+        self.state.cell_widths = get_cell_widths(&self.info.data, self.cell_widths_width);
     }
 }
 
@@ -272,30 +810,108 @@ impl Component for ScrollableTableComponent {
     }
 
     fn draw(&mut self, info: ComponentDrawInfo) {
+        let area = info.area;
+        self.table_area = Rect::default();
+        let info = if self.show_detail && !self.is_fetching && !self.data.is_empty() {
+            let areas = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .split(info.area);
+            info.frame.render_widget(self.get_detail_pane(), areas[1]);
+            ComponentDrawInfo {
+                frame: info.frame,
+                area: areas[0],
+            }
+        } else {
+            info
+        };
+        if self.cell_widths_width != info.area.width {
+            self.cell_widths_width = info.area.width;
+            self.calculate_cell_widths();
+        }
+
         match self.is_fetching {
-            true => {
+            true if self.has_partial_data && info.area.height > 1 => {
+                // Part of the result is already fetched, show it with the throbber below
+                let table_area = Rect {
+                    height: info.area.height - 1,
+                    ..info.area
+                };
+                let throbber_area = Rect {
+                    y: info.area.bottom() - 1,
+                    height: 1,
+                    ..info.area
+                };
+                info.frame
+                    .render_stateful_widget(self.get_table(), table_area, &mut self.state);
+                self.table_area = table_area;
                 info.frame.render_stateful_widget(
-                    Throbber::new(self.loader_steps.clone(), Some("Querying...".to_string())),
-                    info.area,
+                    Throbber::new(self.loader_steps.clone(), Some(self.loader_label.clone())),
+                    throbber_area,
                     &mut self.loader_state,
                 );
             }
-            false => {
+            true => {
                 info.frame.render_stateful_widget(
-                    ScrollableTable::new(
-                        self.info.data.rows.clone(),
-                        self.info.data.header.clone(),
-                    ),
+                    Throbber::new(self.loader_steps.clone(), Some(self.loader_label.clone())),
                     info.area,
-                    &mut self.state,
+                    &mut self.loader_state,
                 );
             }
+            false if self.data.is_empty() => {
+                let message = if let Some(error) = &self.query_error {
+                    format!("Query failed: {}", error)
+                } else if self.has_query_result {
+                    String::from("No documents matched")
+                } else {
+                    format!(
+                        "Press {} to write a query or {} to run the saved one",
+                        KEYMAP.get_key_name(Action::EditQuery),
+                        KEYMAP.get_key_name(Action::RunQuery)
+                    )
+                };
+                // Vertically centered single line
+                let message_area = Rect {
+                    y: info.area.y + info.area.height / 2,
+                    height: info.area.height.min(1),
+                    ..info.area
+                };
+                info.frame.render_widget(
+                    Paragraph::new(message).alignment(Alignment::Center),
+                    message_area,
+                );
+            }
+            false => {
+                info.frame
+                    .render_stateful_widget(self.get_table(), info.area, &mut self.state);
+                self.table_area = info.area;
+            }
+        }
+
+        // Over the whole area, the detail pane included
+        if let Some(picker) = &self.column_picker {
+            picker.draw(info.frame, area, &self.columns, &self.hidden_columns);
+        }
+        if let Some(jump) = &self.column_jump {
+            jump.draw(info.frame, area, &self.get_visible_columns());
         }
     }
 
     fn get_constraint(&self) -> Constraint {
         self.info.constraint
     }
+
+    fn is_focusable(&self) -> bool {
+        self.info.focusable
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.info.is_focused = focused;
+    }
+
+    fn is_capturing_input(&self) -> bool {
+        self.column_picker.is_some() || self.column_jump.is_some()
+    }
 }
 
 impl EventHandler for ScrollableTableComponent {
@@ -310,7 +926,8 @@ impl EventHandler for ScrollableTableComponent {
                         .info
                         .event_sender
                         .send(Event::OnAsyncEvent(tokio::spawn(async move {
-                            match connector.lock().await.set_database(&cloned_value).await {
+                            let mut connector = connector.lock().await;
+                            match connector.set_database(&cloned_value).await {
                                 Ok(_) => {
                                     cloned_sender
                                         .send(Event::OnMessage(Message {
@@ -321,6 +938,15 @@ impl EventHandler for ScrollableTableComponent {
                                             severity: Severity::Info,
                                         }))
                                         .unwrap();
+                                    // Status line only shows the database once it's really used
+                                    cloned_sender
+                                        .send(Event::OnConnection(
+                                            ConnectionEvent::SwitchConnection(
+                                                connector.get_info().host.clone(),
+                                                cloned_value,
+                                            ),
+                                        ))
+                                        .unwrap();
                                 }
                                 Err(e) => {
                                     cloned_sender
@@ -334,6 +960,25 @@ impl EventHandler for ScrollableTableComponent {
                         })));
                     log_error!(self.info.event_sender, result.err());
                 }
+                ConnectionEvent::RefreshCollections => {
+                    let connector = self.connector.clone();
+                    let cloned_sender = self.info.event_sender.clone();
+                    self.info
+                        .event_sender
+                        .send(Event::OnAsyncEvent(tokio::spawn(async move {
+                            let message = match connector.lock().await.refresh_collections().await {
+                                Ok(count) => Message {
+                                    value: format!("Listed {} collections for completion", count),
+                                    severity: Severity::Info,
+                                },
+                                Err(e) => Message {
+                                    value: e.to_string(),
+                                    severity: Severity::Error,
+                                },
+                            };
+                            cloned_sender.send(Event::OnMessage(message)).unwrap();
+                        })))?;
+                }
                 ConnectionEvent::Connect(value) => {
                     let connector = self.connector.clone();
                     let cloned_value = value.clone();
@@ -341,13 +986,33 @@ impl EventHandler for ScrollableTableComponent {
                     self.info
                         .event_sender
                         .send(Event::OnAsyncEvent(tokio::spawn(async move {
-                            match connector
-                                .lock()
-                                .await
-                                .set_connection(cloned_value.clone())
-                                .await
+                            let (connector_ref, uri) = (&connector, &cloned_value.uri);
+                            let result = match retry(
+                                || async move {
+                                    connector_ref.lock().await.set_connection(uri.clone()).await
+                                },
+                                |attempt, err| send_retry_message(&cloned_sender, attempt, err),
+                            )
+                            .await
                             {
+                                Ok(mut info) => match cloned_value.database {
+                                    Some(database) => {
+                                        let mut connector = connector.lock().await;
+                                        connector.set_database(&database).await.map(|_| {
+                                            info.database = database;
+                                            info
+                                        })
+                                    }
+                                    None => Ok(info),
+                                },
+                                Err(e) => Err(e),
+                            };
+                            match result {
                                 Ok(info) => {
+                                    DEBUG_FILE.write_log(&format!(
+                                        "Connected to {}",
+                                        info.redacted_uri()
+                                    ));
                                     cloned_sender
                                         .send(Event::OnMessage(Message {
                                             value: format!(
@@ -372,61 +1037,267 @@ impl EventHandler for ScrollableTableComponent {
                             };
                         })));
                 }
+                ConnectionEvent::SwitchConnection(host, database) => {
+                    self.load_query(host, database);
+                }
                 _ => (),
             },
             Event::OnInput(value) => {
-                if matches!(value.mode, crate::application::Mode::View) {
+                if self.column_picker.is_some() {
+                    self.on_column_picker_key(value.key.code);
+                } else if self.column_jump.is_some() {
+                    self.on_column_jump_key(value.key.code);
+                } else if matches!(value.mode, crate::application::Mode::View) {
+                    let pending_key = self.pending_key.take();
                     match value.key.code {
-                        event::KeyCode::Char('i') => {
+                        // Rebindable keys take precedence over the rest
+                        key if KEYMAP.is_action(key, Action::EditQuery) => {
                             let original_query = self.query.clone();
-                            self.query = EXTERNAL_EDITOR.edit_file(&MONGO_QUERY_FILE).unwrap();
+                            // Edited in place, the LSP finds the collections of the connection
+                            // listed next to the query file. Another tab may have written it
+                            write_file_atomically(&self.query_file, &self.query)?;
+                            self.query = EXTERNAL_EDITOR.edit_file(&self.query_file)?;
                             if original_query == self.query {
                                 value.terminal.lock().unwrap().clear()?;
                                 return Ok(());
                             }
-                            self.reset_state();
-                            self.pagination.reset();
-                            self.spawn_next_data();
+                            self.set_query(self.query.clone());
                             value.terminal.lock().unwrap().clear()?;
                         }
-                        event::KeyCode::Char('r') => {
-                            self.reset_state();
-                            self.pagination.reset();
-                            self.spawn_next_data();
+                        key if KEYMAP.is_action(key, Action::RunQuery) => {
+                            self.run_query();
                             value.terminal.lock().unwrap().clear()?;
                         }
-                        event::KeyCode::Left | event::KeyCode::Char('h') => {
+                        key if key == event::KeyCode::Left
+                            || KEYMAP.is_action(key, Action::MoveLeft) =>
+                        {
                             self.handle_next_horizontal_movement(HorizontalDirection::Left)
                         }
-                        event::KeyCode::Right | event::KeyCode::Char('l') => {
+                        key if key == event::KeyCode::Right
+                            || KEYMAP.is_action(key, Action::MoveRight) =>
+                        {
                             self.handle_next_horizontal_movement(HorizontalDirection::Right)
                         }
-                        event::KeyCode::Down | event::KeyCode::Char('j') => {
+                        key if key == event::KeyCode::Down
+                            || KEYMAP.is_action(key, Action::MoveDown) =>
+                        {
                             self.handle_next_vertical_movement(VerticalDirection::Down)
                         }
-                        event::KeyCode::Up | event::KeyCode::Char('k') => {
+                        key if key == event::KeyCode::Up
+                            || KEYMAP.is_action(key, Action::MoveUp) =>
+                        {
                             self.handle_next_vertical_movement(VerticalDirection::Up)
                         }
-                        event::KeyCode::Enter => {
-                            if self.data.len() > 0 {
-                                let data = self.data[self.state.get_vertical_select() - 1
-                                    + self.state.get_vertical_offset()]
-                                .clone();
-                                EXTERNAL_EDITOR.edit_value(
-                                    &mut serde_json::to_string_pretty(
-                                        &Into::<serde_json::Value>::into(data),
-                                    )?,
-                                    FileType::Json,
+                        event::KeyCode::Char('g') => {
+                            if pending_key == Some('g') {
+                                self.jump_to_top();
+                            } else {
+                                self.pending_key = Some('g');
+                            }
+                        }
+                        event::KeyCode::Char('G') => self.jump_to_bottom(),
+                        event::KeyCode::Char('y') => {
+                            let key = self.copy_selected_cell()?;
+                            self.info
+                                .event_sender
+                                .send(Event::OnMessage(Message {
+                                    value: format!("Value of '{}' copied to clipboard", key),
+                                    severity: Severity::Info,
+                                }))
+                                .unwrap();
+                        }
+                        event::KeyCode::Char('Y') => {
+                            let message = match self.get_selected_document() {
+                                Some(document) => {
+                                    let json = document.into_extended_json();
+                                    self.set_clipboard(serde_json::to_string_pretty(&json)?)?;
+                                    "Document copied to clipboard"
+                                }
+                                None => "Nothing to copy",
+                            };
+                            self.info
+                                .event_sender
+                                .send(Event::OnMessage(Message {
+                                    value: message.to_string(),
+                                    severity: Severity::Info,
+                                }))
+                                .unwrap();
+                        }
+                        event::KeyCode::Char('Q') => {
+                            let message = if self.query.is_empty() {
+                                String::from("Nothing to copy")
+                            } else {
+                                let query = get_canonical_query(&self.query, self.pagination)?;
+                                self.set_clipboard(query)?;
+                                String::from("Query copied to clipboard")
+                            };
+                            self.info
+                                .event_sender
+                                .send(Event::OnMessage(Message {
+                                    value: message,
+                                    severity: Severity::Info,
+                                }))
+                                .unwrap();
+                        }
+                        event::KeyCode::Home | event::KeyCode::Char('0') => {
+                            self.set_horizontal_position(0)
+                        }
+                        event::KeyCode::End | event::KeyCode::Char('$') => {
+                            self.set_horizontal_position(self.horizontal_offset_max)
+                        }
+                        event::KeyCode::Char('o') => {
+                            // Only for viewing, changes made in the editor are discarded
+                            EXTERNAL_EDITOR
+                                .edit_value(&mut self.query.clone(), FileType::Javascript)?;
+                        }
+                        event::KeyCode::Esc if self.is_fetching && self.cancel_query() => {
+                            self.info
+                                .event_sender
+                                .send(Event::OnMessage(Message {
+                                    value: "Query cancelled".to_string(),
+                                    severity: Severity::Info,
+                                }))
+                                .unwrap();
+                        }
+                        event::KeyCode::Char('[') => {
+                            self.cycle_query_history(true)?;
+                            value.terminal.lock().unwrap().clear()?;
+                        }
+                        event::KeyCode::Char(']') => {
+                            self.cycle_query_history(false)?;
+                            value.terminal.lock().unwrap().clear()?;
+                        }
+                        event::KeyCode::Char('c') => {
+                            self.count_documents = !self.count_documents;
+                            self.info.event_sender.send(Event::OnMessage(Message {
+                                value: format!(
+                                    "Counting of matching documents {}",
+                                    if self.count_documents {
+                                        "enabled"
+                                    } else {
+                                        "disabled"
+                                    }
+                                ),
+                                severity: Severity::Info,
+                            }))?;
+                            self.spawn_document_count();
+                        }
+                        event::KeyCode::Char('C') => {
+                            if self.columns.is_empty() {
+                                self.info.event_sender.send(Event::OnMessage(Message {
+                                    value: String::from("No columns to choose from"),
+                                    severity: Severity::Info,
+                                }))?;
+                            } else {
+                                self.column_picker = Some(ColumnPicker::default());
+                            }
+                        }
+                        event::KeyCode::Char('f') => {
+                            if self.info.data.header.cells.is_empty() {
+                                self.info.event_sender.send(Event::OnMessage(Message {
+                                    value: String::from("No columns to jump to"),
+                                    severity: Severity::Info,
+                                }))?;
+                            } else {
+                                self.column_jump = Some(ColumnJump::default());
+                            }
+                        }
+                        event::KeyCode::Char('P') => {
+                            // Hidden columns are no longer fetched at all
+                            let query =
+                                set_find_projection(&self.query, &self.get_visible_columns())?;
+                            self.set_query(query);
+                        }
+                        event::KeyCode::Char('s') => match get_sample_query(&self.query) {
+                            Some(query) => self.set_query(query),
+                            None => {
+                                self.info.event_sender.send(Event::OnMessage(Message {
+                                    value: String::from(
+                                        "Collection of the query is unknown, nothing to sample",
+                                    ),
+                                    severity: Severity::Info,
+                                }))?;
+                            }
+                        },
+                        event::KeyCode::Char('x') => {
+                            self.open_extended_json = !self.open_extended_json;
+                            self.info.event_sender.send(Event::OnMessage(Message {
+                                value: format!(
+                                    "Documents are opened as {}",
+                                    if self.open_extended_json {
+                                        "Extended JSON"
+                                    } else {
+                                        "JSON"
+                                    }
+                                ),
+                                severity: Severity::Info,
+                            }))?;
+                        }
+                        event::KeyCode::Char('T') => {
+                            self.relative_dates = !self.relative_dates;
+                            self.refresh_table_data();
+                            self.info.event_sender.send(Event::OnMessage(Message {
+                                value: format!(
+                                    "Dates are shown as {}",
+                                    if self.relative_dates {
+                                        "relative time"
+                                    } else {
+                                        "ISO 8601"
+                                    }
+                                ),
+                                severity: Severity::Info,
+                            }))?;
+                        }
+                        event::KeyCode::Enter => self.toggle_detail(),
+                        event::KeyCode::Char('J') if self.show_detail => {
+                            self.detail_scroll = self.detail_scroll.saturating_add(1)
+                        }
+                        event::KeyCode::Char('K') if self.show_detail => {
+                            self.detail_scroll = self.detail_scroll.saturating_sub(1)
+                        }
+                        event::KeyCode::Char('E') => {
+                            if let Some(document) = self.get_selected_document() {
+                                self.opened_documents.push(OpenedDocument {
+                                    collection: get_collection_name(&self.query),
+                                    document: document.clone(),
+                                });
+                                let json = serde_json::to_string_pretty(
+                                    &self.get_document_json(document),
                                 )?;
+                                let edited_json = EXTERNAL_EDITOR
+                                    .edit_value(&mut json.clone(), FileType::Json)?;
+                                if edited_json.trim_end() != json.trim_end() {
+                                    self.info.event_sender.send(Event::OnMessage(Message {
+                                        value: String::from(
+                                            "Changes to the document are not saved, updating documents is not supported yet",
+                                        ),
+                                        severity: Severity::Info,
+                                    }))?;
+                                }
                             }
                         }
                         _ => {}
                     }
                 }
             }
+            Event::OnQuery(query) => self.set_query(query.clone()),
+            Event::OnMouse(mouse) => self.on_mouse(*mouse),
+            Event::OnDescribe(name) => self.spawn_describe(name.clone()),
             Event::DatabaseData(value) => {
+                let query_start = self.query_handle.as_ref().map(|(_, start)| *start);
+                if query_start != Some(value.fetch_start) {
+                    // Results of a cancelled or replaced query
+                    return Ok(());
+                }
+                if let Some(error) = &value.error {
+                    self.query_error = Some(error.clone());
+                    self.is_fetching = false;
+                    self.has_partial_data = false;
+                    return Ok(());
+                }
                 log_error!(self.info.event_sender, self.set_data(value.clone()).err());
-                self.is_fetching = false;
+                self.is_fetching = value.is_partial;
+                self.has_partial_data = value.is_partial;
             }
             _ => {}
         }
@@ -434,46 +1305,414 @@ impl EventHandler for ScrollableTableComponent {
     }
 }
 
-impl<'a> From<DatabaseData> for TableData<'a> {
-    fn from(value: DatabaseData) -> Self {
-        let mut header = Row::default();
-        let mut body = Vec::new();
+/// Failed fetch still ends the loading, with the error instead of the data
+fn send_fetch_result(
+    sender: &Sender<Event>,
+    fetch_start: SystemTime,
+    result: Result<DatabaseData>,
+) {
+    match result {
+        Ok(data) => {
+            sender
+                .send(Event::DatabaseData(DatabaseFetchResult {
+                    data,
+                    fetch_start,
+                    trigger_query_took_message: true,
+                    is_partial: false,
+                    error: None,
+                }))
+                .unwrap();
+        }
+        Err(err) => {
+            DEBUG_FILE.write_log(&err);
+            sender
+                .send(Event::DatabaseData(DatabaseFetchResult {
+                    data: DatabaseData(Vec::new()),
+                    fetch_start,
+                    trigger_query_took_message: false,
+                    is_partial: false,
+                    error: Some(err.to_string()),
+                }))
+                .unwrap();
+            log_error!(sender, Some(err));
+        }
+    };
+}
 
-        if !value.is_empty() {
-            let mut unique_keys = value
-                .iter()
-                .fold(HashSet::new(), |mut acc, value| {
-                    acc.extend(value.keys().cloned());
+fn send_retry_message(sender: &Sender<Event>, attempt: u32, err: &anyhow::Error) {
+    sender
+        .send(Event::OnMessage(Message {
+            value: format!("{}, retrying ({}/{})", err, attempt, CLI_ARGS.retries),
+            severity: Severity::Info,
+        }))
+        .unwrap();
+}
 
-                    acc
-                })
-                .into_iter()
-                .collect::<Vec<String>>();
-            unique_keys.sort_by_key(|a| a.len());
-
-            body = value
-                .into_iter()
-                .map(|value| {
-                    //TODO: Error handling
-                    let mut obj = try_from!(<Object>(value)).unwrap();
-
-                    Row::new(unique_keys.iter().fold(Vec::new(), |mut acc, key| {
-                        if obj.contains_key(key) {
-                            acc.push(
-                                Into::<serde_json::Value>::into(obj.remove(key).unwrap())
-                                    .to_string(),
-                            );
-                        } else {
-                            acc.push("".to_string());
-                        }
+/// Collection the query runs on, e.g. `users` of `db.users.find({})`
+fn get_collection_name(query: &str) -> Option<String> {
+    get_query_collection(query).map(|(_, collection)| collection)
+}
+
+/// Collection the query runs on with the database of `db.getSiblingDB("name")`, when the query
+/// switches it
+fn get_query_collection(query: &str) -> Option<(Option<String>, String)> {
+    let tokens = Lexer::new(query.to_string()).scan_tokens().ok()?;
+    let (database, rest) = match tokens.as_slice() {
+        [Token {
+            literal: Some(Literal::String(db)),
+            ..
+        }, Token {
+            r#type: TokenType::Dot,
+            ..
+        }, Token {
+            literal: Some(Literal::String(method)),
+            ..
+        }, Token {
+            r#type: TokenType::LeftParen,
+            ..
+        }, Token {
+            r#type: TokenType::String,
+            literal: Some(Literal::String(database)),
+            ..
+        }, Token {
+            r#type: TokenType::RightParen,
+            ..
+        }, rest @ ..]
+            if db == "db" && method == "getSiblingDB" =>
+        {
+            (Some(database.clone()), rest)
+        }
+        [Token {
+            literal: Some(Literal::String(db)),
+            ..
+        }, rest @ ..]
+            if db == "db" =>
+        {
+            (None, rest)
+        }
+        _ => return None,
+    };
+
+    match rest {
+        [Token {
+            r#type: TokenType::Dot,
+            ..
+        }, Token {
+            r#type: TokenType::Identifier,
+            literal: Some(Literal::String(collection)),
+            ..
+        }, ..] => Some((database, collection.clone())),
+        _ => None,
+    }
+}
+
+/// Samples the collection the query runs on, in the same database
+fn get_sample_query(query: &str) -> Option<String> {
+    let (database, collection) = get_query_collection(query)?;
+    let database = database
+        .map(|database| format!("getSiblingDB({}).", serde_json::Value::String(database)))
+        .unwrap_or_default();
+
+    Some(format!(
+        "db.{}{}.aggregate([{{$sample: {{size: {}}}}}])",
+        database, collection, CLI_ARGS.sample_size
+    ))
+}
 
-                        acc
-                    }))
+/// Names the method called first in the query, e.g. `find` of `db.users.find({})`
+fn get_loader_label(query: &str) -> String {
+    let command = Lexer::new(query.to_string())
+        .scan_tokens()
+        .ok()
+        .and_then(|tokens| {
+            tokens
+                .windows(2)
+                .find_map(|pair| match (&pair[0], &pair[1].r#type) {
+                    (
+                        Token {
+                            r#type: TokenType::Identifier,
+                            literal: Some(Literal::String(name)),
+                            ..
+                        },
+                        TokenType::LeftParen,
+                    ) => Some(name.clone()),
+                    _ => None,
                 })
-                .collect::<Vec<Row>>();
-            header = Row::new(unique_keys.clone());
+        });
+
+    match command {
+        Some(command) => format!("Running {}...", command),
+        None => String::from("Querying..."),
+    }
+}
+
+/// Nested values longer than this are only summarized, the detail pane shows the whole document
+const NESTED_VALUE_MAX_WIDTH: usize = 30;
+
+/// How the values are shown in the table, the documents themselves are not changed
+#[derive(Clone, Copy, Default)]
+struct CellFormat {
+    thousands_separator: Option<char>,
+    /// Dates are shown relative to this time, when it's set
+    relative_to: Option<DateTime<Utc>>,
+}
+
+fn get_cell_content(value: DatabaseValue, format: CellFormat) -> String {
+    match (&value, format.thousands_separator, format.relative_to) {
+        (DatabaseValue::Number(Number::I64(number)), Some(separator), _) => {
+            return group_digits(&number.to_string(), separator)
+        }
+        (DatabaseValue::Number(Number::I32(number)), Some(separator), _) => {
+            return group_digits(&number.to_string(), separator)
+        }
+        (DatabaseValue::DateTime(date_time), _, Some(now)) => {
+            return get_relative_time(*date_time, now)
         }
+        _ => {}
+    }
+
+    if let DatabaseValue::Array(arr) = &value {
+        if is_document_array(&value) {
+            let unit = if arr.len() == 1 { "doc" } else { "docs" };
+            return format!("[{} {}] ⏎", arr.len(), unit);
+        }
+    }
+
+    let summary = match &value {
+        DatabaseValue::Object(obj) => format!("{{…}} {} fields ⏎", obj.len()),
+        DatabaseValue::Array(arr) => format!("[…] {} items ⏎", arr.len()),
+        _ => String::new(),
+    };
+    let content = Into::<serde_json::Value>::into(value).to_string();
+
+    if !summary.is_empty() && content.chars().count() > NESTED_VALUE_MAX_WIDTH {
+        return summary;
+    }
+    content
+}
+
+/// Joined documents, e.g. of `$lookup`, they are summarized whatever their length
+fn is_document_array(value: &DatabaseValue) -> bool {
+    match value {
+        DatabaseValue::Array(arr) => {
+            !arr.is_empty()
+                && arr
+                    .iter()
+                    .all(|item| matches!(item, DatabaseValue::Object(_)))
+        }
+        _ => false,
+    }
+}
+
+/// Inserts the separator between every three digits from the right, the sign is kept in front
+fn group_digits(number: &str, separator: char) -> String {
+    let (sign, digits) = match number.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", number),
+    };
+    let mut grouped = String::from(sign);
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+
+    grouped
+}
+
+/// Time between the date and now in its largest unit, months and years are approximate
+fn get_relative_time(date_time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - date_time).num_seconds();
+    let elapsed = seconds.unsigned_abs();
+    let (amount, unit) = match elapsed {
+        0..=59 => return String::from("just now"),
+        60..=3599 => (elapsed / 60, "minute"),
+        3600..=86399 => (elapsed / 3600, "hour"),
+        86400..=2591999 => (elapsed / 86400, "day"),
+        2592000..=31535999 => (elapsed / 2592000, "month"),
+        _ => (elapsed / 31536000, "year"),
+    };
+    let unit = if amount == 1 {
+        unit.to_string()
+    } else {
+        format!("{}s", unit)
+    };
+
+    if seconds < 0 {
+        format!("in {} {}", amount, unit)
+    } else {
+        format!("{} {} ago", amount, unit)
+    }
+}
+
+/// Keys of all documents, shorter ones first
+fn get_columns(data: &DatabaseData) -> Vec<String> {
+    let mut columns = data
+        .iter()
+        .fold(HashSet::new(), |mut acc, value| {
+            acc.extend(value.keys().cloned());
+
+            acc
+        })
+        .into_iter()
+        .collect::<Vec<String>>();
+    columns.sort_by_key(|a| a.len());
+
+    columns
+}
+
+/// Columns are as wide as their average value, but never wider than the table. The last column
+/// takes the rest of the table
+fn get_cell_widths(data: &TableData, table_width: u16) -> Vec<u16> {
+    data.header
+        .cells
+        .iter()
+        .enumerate()
+        .map(|(idx, cell)| {
+            if data.header.cells.len() - 1 == idx {
+                return table_width;
+            }
+            let value_widths = data
+                .rows
+                .iter()
+                .map(|r| r.cells[idx].content.width() as u16)
+                .collect::<Vec<_>>();
+
+            let mut cell_width: u16 = 0;
+            let mut size = 0;
+            for width in value_widths.iter() {
+                if width >= &100 {
+                    continue;
+                }
+                if let Some(value) = cell_width.checked_add(*width) {
+                    cell_width = value;
+                    size += 1;
+                }
+            }
+            let cell_avg_width = cell_width.checked_div(size).unwrap_or(0);
+            let header_cell_width = cmp::min(cell.content.width(), 30) as u16;
+
+            cmp::min(cmp::max(header_cell_width, cell_avg_width), table_width)
+        })
+        .collect::<Vec<_>>()
+}
+
+fn get_table_data<'a>(
+    value: DatabaseData,
+    columns: &[String],
+    format: CellFormat,
+) -> TableData<'a> {
+    let mut header = Row::default();
+    let mut body = Vec::new();
+
+    if !value.is_empty() {
+        body = value
+            .into_iter()
+            .map(|value| {
+                //TODO: Error handling
+                let mut obj = try_from!(<Object>(value)).unwrap();
+
+                Row::new(columns.iter().fold(Vec::new(), |mut acc, key| {
+                    if obj.contains_key(key) {
+                        acc.push(get_cell_content(obj.remove(key).unwrap(), format));
+                    } else {
+                        acc.push("".to_string());
+                    }
+
+                    acc
+                }))
+            })
+            .collect::<Vec<Row>>();
+        header = Row::new(columns.to_vec());
+    }
+
+    TableData { header, rows: body }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    #[test]
+    fn last_column_narrows_with_table() {
+        let data = TableData {
+            header: Row::new(["name", "description"]),
+            rows: vec![Row::new(["Prague", "Capital of the Czech Republic"])],
+        };
+
+        assert_eq!(get_cell_widths(&data, 80), vec![6, 80]);
+        assert_eq!(get_cell_widths(&data, 40), vec![6, 40]);
+        assert_eq!(get_cell_widths(&data, 4), vec![4, 4]);
+    }
+
+    #[test]
+    fn sibling_database_is_skipped() {
+        assert_eq!(
+            get_query_collection("db.users.find({})"),
+            Some((None, String::from("users")))
+        );
+        assert_eq!(
+            get_query_collection("db.getSiblingDB('logs').events.find({})"),
+            Some((Some(String::from("logs")), String::from("events")))
+        );
+        assert_eq!(get_collection_name("db.getSiblingDB(\"logs\")"), None);
+    }
+
+    #[test]
+    fn digits_are_grouped() {
+        assert_eq!(group_digits("1234567", ','), "1,234,567");
+        assert_eq!(group_digits("-123456", ' '), "-123 456");
+        assert_eq!(group_digits("999", ','), "999");
+    }
+
+    #[test]
+    fn joined_documents_are_summarized() {
+        let document = || DatabaseValue::Object(Object(HashMap::new()));
+
+        assert_eq!(
+            get_cell_content(
+                DatabaseValue::Array(vec![document(), document(), document()]),
+                CellFormat::default()
+            ),
+            "[3 docs] ⏎"
+        );
+        assert_eq!(
+            get_cell_content(
+                DatabaseValue::Array(vec![document()]),
+                CellFormat::default()
+            ),
+            "[1 doc] ⏎"
+        );
+        assert_eq!(
+            get_cell_content(
+                DatabaseValue::Array(vec![document(), DatabaseValue::Null]),
+                CellFormat::default()
+            ),
+            "[{},null]"
+        );
+    }
+
+    #[test]
+    fn dates_are_relative() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+        let relative = |date_time| {
+            get_cell_content(
+                DatabaseValue::DateTime(date_time),
+                CellFormat {
+                    relative_to: Some(now),
+                    ..Default::default()
+                },
+            )
+        };
 
-        TableData { header, rows: body }
+        assert_eq!(relative(now - Duration::seconds(30)), "just now");
+        assert_eq!(relative(now - Duration::hours(1)), "1 hour ago");
+        assert_eq!(relative(now - Duration::days(3)), "3 days ago");
+        assert_eq!(relative(now + Duration::days(800)), "in 2 years");
     }
 }
@@ -1,10 +1,36 @@
-use ratatui::widgets::Paragraph;
+use std::{
+    sync::{mpsc::Sender, Arc},
+    time::Duration,
+};
 
-use super::base::{Component, ComponentCreateInfo};
-use crate::managers::event_manager::{ConnectionEvent, Event, EventHandler};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use tokio::{sync::Mutex, time};
+
+use super::{
+    base::{Component, ComponentCreateInfo},
+    command::{Message, Severity},
+};
+use crate::{
+    connectors::base::Connector,
+    managers::event_manager::{ConnectionEvent, Event, EventHandler},
+    ui::layouts::CLI_ARGS,
+};
+
+/// How often the connection is pinged
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Ping taking longer than this marks the connection as degraded
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct StatusLineComponent {
     info: ComponentCreateInfo<StatusLineData>,
+    /// Documents matching the current query, only counted when enabled in the table
+    document_count: Option<u64>,
+    /// Result of the last ping, `None` until the current connection is pinged
+    is_healthy: Option<bool>,
 }
 
 pub struct StatusLineData {
@@ -27,18 +53,34 @@ impl Component for StatusLineComponent {
     }
 
     fn draw(&mut self, info: super::base::ComponentDrawInfo) {
-        info.frame
-            .render_widget(Paragraph::new(self.get_status_string()), info.area);
+        let health_color = match self.is_healthy {
+            Some(true) => Color::Green,
+            Some(false) => Color::Red,
+            None => Color::DarkGray,
+        };
+        info.frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled("●", Style::default().fg(health_color)),
+                Span::raw(self.get_status_string()),
+            ])),
+            info.area,
+        );
     }
 }
 
 impl EventHandler for StatusLineComponent {
     fn on_event(&mut self, event: &Event) -> anyhow::Result<()> {
-        if let Event::OnConnection(ConnectionEvent::SwitchDatabase(value)) = event {
-            self.info.data.database_name = value.clone();
-        } else if let Event::OnConnection(ConnectionEvent::SwitchConnection(host, db)) = event {
-            self.info.data.host = host.clone();
-            self.info.data.database_name = db.clone();
+        match event {
+            Event::OnConnection(ConnectionEvent::SwitchConnection(host, db)) => {
+                self.info.data.host = host.clone();
+                self.info.data.database_name = db.clone();
+                self.is_healthy = None;
+            }
+            Event::OnConnection(ConnectionEvent::Health(is_healthy)) => {
+                self.is_healthy = Some(*is_healthy)
+            }
+            Event::OnDocumentCount(count) => self.document_count = *count,
+            _ => (),
         }
         Ok(())
     }
@@ -46,12 +88,77 @@ impl EventHandler for StatusLineComponent {
 
 impl StatusLineComponent {
     pub fn new(info: ComponentCreateInfo<StatusLineData>) -> Self {
-        Self { info }
+        Self {
+            info,
+            document_count: None,
+            is_healthy: None,
+        }
     }
 
     fn get_status_string(&self) -> String {
         let database_name = format!(" {}", self.info.data.host);
 
-        [database_name, self.info.data.database_name.clone()].join(" | ")
+        let mut segments = vec![database_name, self.info.data.database_name.clone()];
+        if let Some(count) = self.document_count {
+            segments.push(format!("{} documents", count));
+        }
+        if CLI_ARGS.read_only {
+            segments.push(String::from("read-only"));
+        }
+        if CLI_ARGS.dry_run {
+            segments.push(String::from("dry run"));
+        }
+
+        segments.join(" | ")
     }
 }
+
+/// Pings the database in the background, the health shown in the status line changes with the
+/// result and a message is sent when the connection is lost or restored
+pub fn spawn_health_check(connector: Arc<Mutex<dyn Connector>>, sender: Sender<Event>) {
+    tokio::spawn(async move {
+        let mut interval = time::interval(HEALTH_CHECK_INTERVAL);
+        let mut was_healthy = true;
+        loop {
+            interval.tick().await;
+
+            // Connector is locked while a query runs, which shows it's reachable anyway
+            let Ok(connector) = connector.try_lock() else {
+                continue;
+            };
+            let result = match time::timeout(HEALTH_CHECK_TIMEOUT, connector.ping()).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow::anyhow!("Ping timed out")),
+            };
+            drop(connector);
+
+            // Sent every time, the health is unknown again after switching the connection
+            let is_healthy = result.is_ok();
+            if sender
+                .send(Event::OnConnection(ConnectionEvent::Health(is_healthy)))
+                .is_err()
+            {
+                // Application has exited
+                return;
+            }
+            if is_healthy == was_healthy {
+                continue;
+            }
+            was_healthy = is_healthy;
+
+            let message = match result {
+                Ok(()) => Message {
+                    value: String::from("Connection restored"),
+                    severity: Severity::Info,
+                },
+                Err(err) => Message {
+                    value: format!("Connection lost: {}", err),
+                    severity: Severity::Error,
+                },
+            };
+            if sender.send(Event::OnMessage(message)).is_err() {
+                return;
+            }
+        }
+    });
+}
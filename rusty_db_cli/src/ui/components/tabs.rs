@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use crossterm::event;
+use ratatui::{
+    layout::{Constraint, Rect},
+    widgets::Tabs,
+};
+use tokio::sync::Mutex;
+
+use super::{
+    base::{Component, ComponentCreateInfo, ComponentDrawInfo},
+    command::{Message, Severity},
+    message_log::MessageLog,
+    scrollable_table::ScrollableTableComponent,
+};
+use crate::{
+    application::Mode,
+    connectors::base::{Connector, ConnectorInfo, TableData},
+    managers::event_manager::{ConnectionEvent, Event, EventHandler},
+    utils::theme::THEME,
+    widgets::scrollable_table::ScrollableTableState,
+};
+
+/// Tables with independent queries and results, only the selected one is visible. The tab bar
+/// is shown once there is more than one tab
+pub struct TabsComponent {
+    info: ComponentCreateInfo<()>,
+    tabs: Vec<ScrollableTableComponent>,
+    active_tab: usize,
+    connector: Arc<Mutex<dyn Connector>>,
+    /// Connection the query of a new tab is loaded for
+    connection: ConnectorInfo,
+    /// Drawn over the tabs while it's open
+    message_log: MessageLog,
+}
+
+impl TabsComponent {
+    pub fn new(
+        info: ComponentCreateInfo<()>,
+        connector: Arc<Mutex<dyn Connector>>,
+        connection: ConnectorInfo,
+    ) -> Self {
+        let mut tabs = Self {
+            info,
+            tabs: Vec::new(),
+            active_tab: 0,
+            connector,
+            connection,
+            message_log: MessageLog::default(),
+        };
+        tabs.open_tab();
+
+        tabs
+    }
+
+    fn open_tab(&mut self) {
+        let table = ScrollableTableComponent::new(
+            ComponentCreateInfo {
+                id: self.info.id,
+                constraint: self.info.constraint,
+                data: TableData::default(),
+                focusable: self.info.focusable,
+                visible: true,
+                event_sender: self.info.event_sender.clone(),
+                is_focused: self.info.is_focused,
+            },
+            ScrollableTableState::default(),
+            self.connector.clone(),
+            &self.connection,
+        );
+        self.tabs.push(table);
+        self.select_tab(self.tabs.len() - 1);
+    }
+
+    fn close_tab(&mut self) -> Result<()> {
+        if self.tabs.len() == 1 {
+            self.info.event_sender.send(Event::OnMessage(Message {
+                value: String::from("The last tab can't be closed"),
+                severity: Severity::Info,
+            }))?;
+            return Ok(());
+        }
+
+        self.tabs.remove(self.active_tab).close();
+        self.select_tab(self.active_tab.min(self.tabs.len() - 1));
+
+        Ok(())
+    }
+
+    fn select_tab(&mut self, idx: usize) {
+        for (tab_idx, tab) in self.tabs.iter_mut().enumerate() {
+            tab.set_visibility(tab_idx == idx);
+        }
+        self.active_tab = idx;
+
+        // Count belongs to the query of the previously selected tab
+        self.info
+            .event_sender
+            .send(Event::OnDocumentCount(None))
+            .unwrap();
+        self.info
+            .event_sender
+            .send(Event::OnMessage(Message {
+                value: format!("Tab {}/{}", idx + 1, self.tabs.len()),
+                severity: Severity::Info,
+            }))
+            .unwrap();
+    }
+
+    /// Tab bar above the selected table
+    fn draw_tabs(&mut self, info: ComponentDrawInfo) {
+        let titles = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(idx, tab)| format!("{} {}", idx + 1, tab.get_title()))
+            .collect::<Vec<_>>();
+        info.frame.render_widget(
+            Tabs::new(titles)
+                .select(self.active_tab)
+                .highlight_style(THEME.selection),
+            Rect {
+                height: 1,
+                ..info.area
+            },
+        );
+
+        let table_area = Rect {
+            y: info.area.y + 1,
+            height: info.area.height - 1,
+            ..info.area
+        };
+        self.get_active_tab().draw(ComponentDrawInfo {
+            frame: info.frame,
+            area: table_area,
+        });
+    }
+
+    fn get_active_tab(&mut self) -> &mut ScrollableTableComponent {
+        &mut self.tabs[self.active_tab]
+    }
+}
+
+impl Component for TabsComponent {
+    fn get_constraint(&self) -> Constraint {
+        self.info.constraint
+    }
+
+    fn is_visible(&self) -> bool {
+        self.info.visible
+    }
+
+    fn set_visibility(&mut self, visible: bool) -> bool {
+        self.info.visible = visible;
+        visible
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.info.focusable
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.info.is_focused = focused;
+        for tab in self.tabs.iter_mut() {
+            tab.set_focused(focused);
+        }
+    }
+
+    fn is_capturing_input(&self) -> bool {
+        self.message_log.is_open() || self.tabs[self.active_tab].is_capturing_input()
+    }
+
+    fn draw(&mut self, info: ComponentDrawInfo) {
+        if self.tabs.len() == 1 || info.area.height < 2 {
+            self.get_active_tab().draw(ComponentDrawInfo {
+                frame: info.frame,
+                area: info.area,
+            });
+        } else {
+            self.draw_tabs(ComponentDrawInfo {
+                frame: info.frame,
+                area: info.area,
+            });
+        }
+
+        if self.message_log.is_open() {
+            self.message_log.draw(info.frame, info.area);
+        }
+    }
+}
+
+impl EventHandler for TabsComponent {
+    fn on_event(&mut self, event: &Event) -> Result<()> {
+        if let Event::OnMessage(message) = event {
+            self.message_log.push(message);
+        }
+
+        match event {
+            Event::OnInput(value) if self.message_log.is_open() => {
+                self.message_log.on_key(value.key.code)
+            }
+            Event::OnMouse(_) if self.message_log.is_open() => {}
+            Event::OnInput(_) if self.tabs[self.active_tab].is_capturing_input() => {
+                self.get_active_tab().on_event(event)?
+            }
+            Event::OnInput(value) if matches!(value.mode, Mode::View) => match value.key.code {
+                event::KeyCode::Char('t') => self.open_tab(),
+                event::KeyCode::Char('m') => self.message_log.open(),
+                event::KeyCode::Char('w') => self.close_tab()?,
+                event::KeyCode::Char('}') => {
+                    self.select_tab((self.active_tab + 1) % self.tabs.len());
+                }
+                event::KeyCode::Char('{') => {
+                    self.select_tab((self.active_tab + self.tabs.len() - 1) % self.tabs.len());
+                }
+                _ => self.get_active_tab().on_event(event)?,
+            },
+            // Meant for the table the user works with, switching the database or connection
+            // changes the connector shared by all of the tabs though
+            Event::OnInput(_)
+            | Event::OnMouse(_)
+            | Event::OnQuery(_)
+            | Event::OnDescribe(_)
+            | Event::OnConnection(ConnectionEvent::SwitchDatabase(_))
+            | Event::OnConnection(ConnectionEvent::RefreshCollections)
+            | Event::OnConnection(ConnectionEvent::Connect(_)) => {
+                self.get_active_tab().on_event(event)?
+            }
+            Event::OnConnection(ConnectionEvent::SwitchConnection(host, database)) => {
+                self.connection.host.clone_from(host);
+                self.connection.database.clone_from(database);
+                for tab in self.tabs.iter_mut() {
+                    tab.on_event(event)?;
+                }
+            }
+            // Results are matched to the query of the tab by the tables themselves
+            _ => {
+                for tab in self.tabs.iter_mut() {
+                    tab.on_event(event)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
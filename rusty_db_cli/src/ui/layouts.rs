@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use clap::Parser;
 use once_cell::sync::Lazy;
 use ratatui::layout::Constraint;
@@ -8,18 +6,14 @@ use super::{
     components::{
         base::ComponentCreateInfo,
         command::{CommandComponent, Message},
-        scrollable_table::ScrollableTableComponent,
-        status_line::{StatusLineComponent, StatusLineData},
+        status_line::{spawn_health_check, StatusLineComponent, StatusLineData},
+        tabs::TabsComponent,
     },
     window::{Window, WindowBuilder},
 };
 use crate::{
-    connectors::{
-        base::{Connector, TableData},
-        mongodb::connector::MongodbConnectorBuilder,
-    },
-    managers::event_manager::EventManager,
-    widgets::scrollable_table::ScrollableTableState,
+    connectors::factory::build_connector, managers::event_manager::EventManager,
+    utils::external_editor::DEBUG_FILE,
 };
 
 #[derive(clap::Parser)]
@@ -36,51 +30,113 @@ pub struct CliArgs {
     /// $HOME/.config/rusty-db-cli/.command_history.txt
     #[arg(long, name="disable-command-history", default_value_t = false, action = clap::ArgAction::SetTrue)]
     pub disable_command_history: bool,
+
+    /// Allows running destructive commands, like dropping a collection
+    #[arg(long, default_value_t = false)]
+    pub allow_destructive: bool,
+
+    /// Rejects every command that writes to the database
+    #[arg(long, default_value_t = false, conflicts_with = "allow_destructive")]
+    pub read_only: bool,
+
+    /// Shows the driver call each query translates into instead of running it
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Maximum time in seconds a query can run on the server, unless it sets `.maxTimeMS()` itself
+    #[arg(long, default_value_t = 30)]
+    pub query_timeout: u64,
+
+    /// Maximum time in seconds to find a server the query can be sent to, e.g. the primary
+    #[arg(long, default_value_t = 3)]
+    pub server_selection_timeout: u64,
+
+    /// Maximum time in seconds to open a connection to a server, the time queries may run is
+    /// limited by `--query-timeout`
+    #[arg(long, default_value_t = 10)]
+    pub connect_timeout: u64,
+
+    /// How many times a query or connection failing on a network error is retried, waiting
+    /// twice as long before each retry starting with 500 ms
+    #[arg(long, default_value_t = 2)]
+    pub retries: u32,
+
+    /// Amount of documents fetched per page, must be at least 2
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(2..))]
+    pub page_size: u32,
+
+    /// Separates thousands of integers shown in the table, e.g. `,` shows 1,234,567. Copied
+    /// documents and --query output keep the raw digits
+    #[arg(long)]
+    pub thousands_separator: Option<char>,
+
+    /// Amount of random documents fetched by the `s` keybind
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..))]
+    pub sample_size: u32,
+
+    /// Runs the query without starting the TUI and prints its result to stdout,
+    /// `-` reads the query from stdin
+    #[arg(long)]
+    pub query: Option<String>,
+
+    /// Format of the printed result when running with --query
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub output: OutputFormat,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum OutputFormat {
+    /// Single JSON array with all documents
+    Json,
+    /// One JSON document per line
+    Ndjson,
 }
 
 pub static CLI_ARGS: Lazy<CliArgs> = Lazy::new(CliArgs::parse);
 
-pub async fn get_table_layout() -> Window {
+/// `password` replaces the placeholder of the connection uri, when it has one
+pub async fn get_table_layout(password: Option<String>) -> Window {
     let event_manager = EventManager::new();
 
-    let connector = if CLI_ARGS.database_uri.contains("mongodb") {
-        MongodbConnectorBuilder::new(&CLI_ARGS.database_uri)
-            .build()
-            .await
-    } else {
-        panic!("Other connectors are not implemented");
-    }
+    // Nothing is drawn yet, the retries are only visible in the debug log
+    let connector = build_connector(&CLI_ARGS.database_uri, password, |attempt, err| {
+        DEBUG_FILE.write_log(&format!("Retry {} after: {}", attempt, err))
+    })
+    .await
     .expect("Failed to create DB connector");
+    let connection = connector.lock().await.get_info().clone();
+    spawn_health_check(connector.clone(), event_manager.sender.clone());
 
     let status_line = StatusLineComponent::new(ComponentCreateInfo {
-        focusable: true,
+        focusable: false,
         visible: true,
         constraint: Constraint::Length(1),
         data: StatusLineData {
-            host: connector.get_info().host.clone(),
-            database_name: connector.database.clone(),
+            host: connection.host.clone(),
+            database_name: connection.database.clone(),
         },
         id: 2,
         event_sender: event_manager.sender.clone(),
         is_focused: false,
     });
 
-    let table = ScrollableTableComponent::new(
+    let table = TabsComponent::new(
         ComponentCreateInfo {
             constraint: Constraint::Min(0),
-            data: TableData::default(),
+            data: (),
             focusable: true,
             id: 0,
             visible: true,
             event_sender: event_manager.sender.clone(),
             is_focused: true,
         },
-        ScrollableTableState::default(),
-        Arc::new(tokio::sync::Mutex::new(connector)),
+        connector,
+        connection,
     );
 
+    // Opened with `:` instead, focused it would only drop the keys of the view mode
     let command = CommandComponent::new(ComponentCreateInfo {
-        focusable: true,
+        focusable: false,
         visible: true,
         constraint: Constraint::Length(1),
         data: Message::default(),
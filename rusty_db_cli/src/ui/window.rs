@@ -17,6 +17,7 @@ use crate::{
     application::Mode,
     log_error,
     managers::event_manager::{Event, EventHandler, EventManager},
+    utils::keymap::{Action, KEYMAP},
 };
 
 pub struct WindowRenderInfo {
@@ -122,12 +123,56 @@ impl Window {
             .unwrap();
     }
 
+    /// Focused component takes all keys, including those handled by the window or the application
+    pub fn is_capturing_input(&self) -> bool {
+        self.components[self.focused_component_idx].is_capturing_input()
+    }
+
+    /// Keys of the view mode go to the focused component only, except `:` opening the command
+    /// line. Keys typed into the command line go to all components
     pub fn on_key(&mut self, event: Event) {
+        if let Event::OnInput(input) = &event {
+            if matches!(input.mode, Mode::View) {
+                let is_capturing_input = self.is_capturing_input();
+                match input.key.code {
+                    key if !is_capturing_input
+                        && KEYMAP.is_action(key, Action::OpenCommandLine) => {}
+                    _ => {
+                        let result = self.components[self.focused_component_idx].on_event(&event);
+                        log_error!(self.event_manager.sender, result.err());
+                        return self.pool_events();
+                    }
+                }
+            }
+        }
+
         self.event_manager.sender.send(event).unwrap();
+        self.pool_events();
+    }
+
+    pub fn on_mouse(&mut self, mouse: event::MouseEvent) {
+        self.event_manager
+            .sender
+            .send(Event::OnMouse(mouse))
+            .unwrap();
+        self.pool_events();
+    }
+
+    fn pool_events(&mut self) {
         if let Some(err) = self.event_manager.pool(&mut self.components).err() {
             log_error!(self.event_manager.sender, Some(err))
         }
     }
+
+    pub fn set_focused_component(&mut self, idx: usize) {
+        if idx >= self.components.len() {
+            return;
+        }
+
+        self.components[self.focused_component_idx].set_focused(false);
+        self.focused_component_idx = idx;
+        self.components[idx].set_focused(true);
+    }
 }
 
 pub struct OnInputInfo {
@@ -0,0 +1,291 @@
+use anyhow::Result;
+use mongodb::bson::{to_document, Document};
+use rusty_db_cli_mongo::{
+    lexer::{Token, TokenType},
+    parser::{Expression, Parser},
+    types::{
+        expressions::{CallExpression, Identifier},
+        literals::{Literal, Number},
+    },
+};
+
+use super::projection::parse_tokens;
+use crate::connectors::{base::PaginationInfo, mongodb::connector::is_paginated_pipeline};
+
+/// Member of the query chain, e.g. `db`, `users` or `find({a: 1})`
+struct Segment<'a> {
+    name: String,
+    /// Tokens of each argument, none when the member is not called
+    args: Option<Vec<&'a [Token]>>,
+}
+
+/// Query the way it runs for the page of the table, on one line with normalized whitespace.
+/// Pagination of find and aggregate queries replaces their own `.skip()` and `.limit()`, e.g.
+/// `db.users.find({a: 1}).skip(20).limit(21)`
+pub fn get_canonical_query(query: &str, pagination: PaginationInfo) -> Result<String> {
+    let tokens = parse_tokens(query)?;
+    let segments = get_segments(&tokens);
+    let mut members = segments.iter().map(print_segment).collect::<Vec<_>>();
+
+    let command = segments
+        .iter()
+        .position(|segment| segment.args.is_some() && segment.name != "getSiblingDB");
+    if let Some(command) = command {
+        let subcommands = &segments[command + 1..];
+        let has_subcommand = |names: &[&str]| {
+            subcommands
+                .iter()
+                .any(|segment| names.contains(&segment.name.to_lowercase().as_str()))
+        };
+        let limit = get_number_argument(subcommands, "limit").unwrap_or(pagination.limit as i64);
+
+        // Same as the pagination of the queries, find skips by the page only
+        let page = match (segments[command].name.as_str(), &segments[command].args) {
+            ("find", _) if !has_subcommand(&["tail", "count", "explain"]) => {
+                Some((pagination.start as i64, limit))
+            }
+            ("aggregate", Some(args))
+                if !has_subcommand(&["explain"])
+                    && args.first().is_some_and(|pipeline| is_paginated(pipeline)) =>
+            {
+                let skip = get_number_argument(subcommands, "skip").unwrap_or(0);
+                Some((pagination.start as i64 + skip, limit))
+            }
+            _ => None,
+        };
+
+        if let Some((skip, limit)) = page {
+            members = members
+                .into_iter()
+                .zip(&segments)
+                .enumerate()
+                .filter(|(idx, (_, segment))| {
+                    *idx <= command
+                        || !matches!(segment.name.to_lowercase().as_str(), "skip" | "limit")
+                })
+                .map(|(_, (member, _))| member)
+                .collect();
+            if skip > 0 {
+                members.push(format!("skip({})", skip));
+            }
+            members.push(format!("limit({})", limit));
+        }
+    }
+
+    Ok(members.join("."))
+}
+
+/// Members of the first statement, split by the dots between them
+fn get_segments(tokens: &[Token]) -> Vec<Segment<'_>> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut idx = 0;
+    while let Some(token) = tokens.get(idx) {
+        match token.r#type {
+            TokenType::Semicolon | TokenType::Eof => break,
+            TokenType::LeftParen => {
+                let end = get_closing_bracket(tokens, idx);
+                if let Some(segment) = segments.last_mut() {
+                    segment.args = Some(get_items(&tokens[idx..=end]));
+                }
+                idx = end + 1;
+                continue;
+            }
+            TokenType::Dot => {}
+            _ => segments.push(Segment {
+                name: token.lexeme().to_string(),
+                args: None,
+            }),
+        }
+        idx += 1;
+    }
+
+    segments
+}
+
+fn get_closing_bracket(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate().skip(open) {
+        match token.r#type {
+            TokenType::LeftParen | TokenType::LeftBrace | TokenType::LeftBracket => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace | TokenType::RightBracket => {
+                depth -= 1;
+                if depth == 0 {
+                    return idx;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tokens.len() - 1
+}
+
+/// Comma separated items between the brackets the tokens start and end with
+fn get_items(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut items = Vec::new();
+    let mut start = 1;
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate().take(tokens.len() - 1).skip(1) {
+        match token.r#type {
+            TokenType::LeftParen | TokenType::LeftBrace | TokenType::LeftBracket => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace | TokenType::RightBracket => depth -= 1,
+            TokenType::Comma if depth == 0 => {
+                items.push(&tokens[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < tokens.len() - 1 {
+        items.push(&tokens[start..tokens.len() - 1]);
+    }
+
+    items
+}
+
+/// Same rule as the pagination of the aggregate query, the pipeline is evaluated as its stages
+fn is_paginated(pipeline: &[Token]) -> bool {
+    get_stages(pipeline).is_some_and(|stages| is_paginated_pipeline(&stages))
+}
+
+/// Stages of the pipeline as documents, parsed again from its normalized tokens
+fn get_stages(pipeline: &[Token]) -> Option<Vec<Document>> {
+    let tokens = parse_tokens(&format!("aggregate({})", print_tokens(pipeline))).ok()?;
+    let mut program = Parser::new(tokens).parse().ok()?;
+    let Some(Expression::ExpressionStatement(statement)) = program.body.pop() else {
+        return None;
+    };
+    let CallExpression::Primary(call) = statement.expression else {
+        return None;
+    };
+    let Some(Identifier::Array(array)) = call.params.params.first() else {
+        return None;
+    };
+
+    array
+        .elements
+        .iter()
+        .map(|stage| match stage {
+            Identifier::Object(stage) => to_document(stage).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Integer argument of the last subcommand with the name
+fn get_number_argument(subcommands: &[Segment], name: &str) -> Option<i64> {
+    subcommands
+        .iter()
+        .rev()
+        .find(|segment| segment.name.eq_ignore_ascii_case(name))
+        .and_then(|segment| match segment.args.as_deref() {
+            Some(
+                [[Token {
+                    literal: Some(Literal::Number(number)),
+                    ..
+                }]],
+            ) => match number {
+                Number::I32(number) => Some(*number as i64),
+                Number::I64(number) => Some(*number),
+                Number::F64(_) => None,
+            },
+            _ => None,
+        })
+}
+
+fn print_segment(segment: &Segment) -> String {
+    match &segment.args {
+        Some(args) => format!(
+            "{}({})",
+            segment.name,
+            args.iter()
+                .map(|arg| print_tokens(arg))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        None => segment.name.clone(),
+    }
+}
+
+/// Tokens with a space after commas and colons, strings are double quoted
+fn print_tokens(tokens: &[Token]) -> String {
+    let mut output = String::new();
+    for token in tokens {
+        match (&token.r#type, &token.literal) {
+            (TokenType::String, Some(Literal::String(value))) => {
+                output.push_str(&serde_json::Value::String(value.clone()).to_string())
+            }
+            (TokenType::Comma | TokenType::Colon, _) => {
+                output.push_str(token.lexeme());
+                output.push(' ');
+            }
+            _ => output.push_str(token.lexeme()),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: PaginationInfo = PaginationInfo {
+        start: 0,
+        limit: 50,
+    };
+
+    #[test]
+    fn whitespace_is_normalized() {
+        assert_eq!(
+            get_canonical_query(
+                "db.users.find(\n  {a:1 ,b : 'x', c: /ab/i}\n)\n  .sort({a:-1})",
+                PAGE
+            )
+            .unwrap(),
+            "db.users.find({a: 1, b: \"x\", c: /ab/i}).sort({a: -1}).limit(50)"
+        );
+        assert_eq!(
+            get_canonical_query("db.getSiblingDB('logs').events.countDocuments( )", PAGE).unwrap(),
+            "db.getSiblingDB(\"logs\").events.countDocuments()"
+        );
+    }
+
+    #[test]
+    fn pagination_replaces_skip_and_limit() {
+        let page = PaginationInfo {
+            start: 20,
+            limit: 21,
+        };
+
+        assert_eq!(
+            get_canonical_query("db.users.find().skip(5).limit(10)", page).unwrap(),
+            "db.users.find().skip(20).limit(10)"
+        );
+        assert_eq!(
+            get_canonical_query("db.orders.aggregate([{$match: {}}]).skip(5)", page).unwrap(),
+            "db.orders.aggregate([{$match: {}}]).skip(25).limit(21)"
+        );
+    }
+
+    #[test]
+    fn single_document_pipeline_is_not_paginated() {
+        assert_eq!(
+            get_canonical_query("db.orders.aggregate([{$count: 'total'}])", PAGE).unwrap(),
+            "db.orders.aggregate([{$count: \"total\"}])"
+        );
+        assert_eq!(
+            get_canonical_query(
+                "db.orders.aggregate([{$group: {_id: null, total: {$sum: 1}}}])",
+                PAGE
+            )
+            .unwrap(),
+            "db.orders.aggregate([{$group: {_id: null, total: {$sum: 1}}}])"
+        );
+        assert_eq!(
+            get_canonical_query("db.orders.aggregate([{$match: {}}, {$out: 'copy'}])", PAGE)
+                .unwrap(),
+            "db.orders.aggregate([{$match: {}}, {$out: \"copy\"}])"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+
+use crate::connectors::base::Object;
+
+/// Documents opened in the editor, kept as they were before editing
+pub struct OpenedDocument {
+    /// Collection of the query the document was fetched by, when it can be told from the query
+    pub collection: Option<String>,
+    pub document: Object,
+}
+
+/// Last documents opened in the editor during the session, the oldest ones are dropped once it's
+/// full. Edits can be compared with, or reverted to, the original documents
+pub struct DocumentHistory {
+    documents: VecDeque<OpenedDocument>,
+    capacity: usize,
+}
+
+impl DocumentHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            documents: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, document: OpenedDocument) {
+        if self.documents.len() == self.capacity {
+            self.documents.pop_front();
+        }
+        self.documents.push_back(document);
+    }
+
+    /// Most recently opened document
+    pub fn last(&self) -> Option<&OpenedDocument> {
+        self.documents.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn opened(collection: &str) -> OpenedDocument {
+        OpenedDocument {
+            collection: Some(collection.to_string()),
+            document: Object(HashMap::new()),
+        }
+    }
+
+    #[test]
+    fn oldest_document_is_dropped() {
+        let mut history = DocumentHistory::new(2);
+        history.push(opened("a"));
+        history.push(opened("b"));
+        history.push(opened("c"));
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history
+                .last()
+                .and_then(|opened| opened.collection.as_deref()),
+            Some("c")
+        );
+    }
+}
@@ -1,12 +1,13 @@
 use std::{
     env,
     fmt::Debug,
-    fs::{create_dir, File, OpenOptions},
+    fs::{self, create_dir, File, OpenOptions},
     io::{Read, Write},
     path::{self, Path, PathBuf},
     process::Command,
 };
 
+use anyhow::Context;
 use once_cell::sync::Lazy;
 
 use crate::ui::layouts::CLI_ARGS;
@@ -15,6 +16,7 @@ pub struct ExternalEditor {
     editor: String,
 }
 
+/// Decides the extension of the edited file, which editors use to pick the highlighting
 pub enum FileType {
     Json,
     Javascript,
@@ -42,10 +44,7 @@ impl ExternalEditor {
             .tempfile()?;
         let mut handle = file.reopen()?;
         handle.write_all(value.as_bytes())?;
-        Command::new(&self.editor)
-            .current_dir(".")
-            .arg(file.path())
-            .status()?;
+        self.open(file.path())?;
 
         let mut edited_value = String::new();
         handle.read_to_string(&mut edited_value)?;
@@ -55,40 +54,80 @@ impl ExternalEditor {
         Ok(value.to_string())
     }
 
+    /// Read again by the path, editors often save by replacing the file
     pub fn edit_file(&self, path: &str) -> anyhow::Result<String> {
-        let mut handle = File::open(path)?;
+        self.open(Path::new(path))?;
+
+        Ok(fs::read_to_string(path)?)
+    }
+
+    fn open(&self, path: &Path) -> anyhow::Result<()> {
         Command::new(&self.editor)
             .current_dir(".")
             .arg(path)
-            .status()?;
+            .status()
+            .with_context(|| {
+                format!(
+                    "Failed to open editor '{}', set the EDITOR environment variable",
+                    self.editor
+                )
+            })?;
+
+        Ok(())
+    }
+}
 
-        let mut edited_value = String::new();
-        handle.read_to_string(&mut edited_value)?;
+/// Used when the EDITOR environment variable is not set
+const DEFAULT_EDITOR: &str = "vi";
 
-        Ok(edited_value.to_string())
-    }
+pub static EXTERNAL_EDITOR: Lazy<ExternalEditor> =
+    Lazy::new(|| ExternalEditor::new(&env::var("EDITOR").unwrap_or(DEFAULT_EDITOR.to_string())));
+
+/// Last query is stored separately for each host and database
+pub fn get_query_file(host: &str, database: &str) -> String {
+    get_connection_file(host, database, "js")
 }
 
-pub static EXTERNAL_EDITOR: Lazy<ExternalEditor> = Lazy::new(|| {
-    ExternalEditor::new(
-        &env::vars()
-            .find(|(key, _)| key == "EDITOR")
-            .expect("EDITOR env to be set")
-            .1,
-    )
-});
+/// Queries previously run on the host and database
+pub fn get_query_history_file(host: &str, database: &str) -> String {
+    get_connection_file(host, database, "history.json")
+}
+
+/// Collections of the database, used by the LSP for the completion in the query file
+pub fn get_collections_file(host: &str, database: &str) -> String {
+    get_connection_file(host, database, "collections.txt")
+}
+
+fn get_connection_file(host: &str, database: &str, extension: &str) -> String {
+    let dir = Path::new(CONFIG_PATH.as_str()).join("queries");
+    if !dir.exists() {
+        create_dir(dir.clone()).expect("Failed to create queries dir");
+    }
 
-pub const MONGO_QUERY_FILE: Lazy<String> = Lazy::new(|| {
-    let path = Path::new(CONFIG_PATH.as_str()).join(".mongo.js");
+    let name = format!("{}_{}", host, database)
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    let path = dir.join(format!(".{}.{}", name, extension));
 
     if !path.exists() {
         File::create(path.clone()).expect("Failed to create mongo file");
     }
 
     path.to_str().unwrap().to_string()
-});
+}
+
+/// Writes into a temporary file that then replaces the original one, so it's never half written
+pub fn write_file_atomically(path: &str, content: &str) -> anyhow::Result<()> {
+    let path = Path::new(path);
+    let mut file = tempfile::NamedTempFile::new_in(path.parent().unwrap_or(Path::new(".")))?;
+    file.write_all(content.as_bytes())?;
+    file.persist(path)?;
+
+    Ok(())
+}
 
-pub const MONGO_COLLECTIONS_FILE: Lazy<String> = Lazy::new(|| {
+pub static MONGO_COLLECTIONS_FILE: Lazy<String> = Lazy::new(|| {
     let path = Path::new(CONFIG_PATH.as_str()).join(".collections.txt");
 
     if !path.exists() {
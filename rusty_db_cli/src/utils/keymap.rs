@@ -0,0 +1,234 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use crossterm::event::KeyCode;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use super::external_editor::CONFIG_PATH;
+
+/// Actions that can be bound to another key in the keymap file
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveDown,
+    MoveUp,
+    EditQuery,
+    RunQuery,
+    OpenCommandLine,
+    Quit,
+}
+
+const DEFAULT_KEYS: [(Action, KeyCode); 8] = [
+    (Action::MoveLeft, KeyCode::Char('h')),
+    (Action::MoveRight, KeyCode::Char('l')),
+    (Action::MoveDown, KeyCode::Char('j')),
+    (Action::MoveUp, KeyCode::Char('k')),
+    (Action::EditQuery, KeyCode::Char('i')),
+    (Action::RunQuery, KeyCode::Char('r')),
+    (Action::OpenCommandLine, KeyCode::Char(':')),
+    (Action::Quit, KeyCode::Char('q')),
+];
+
+/// Keys of the keybinds that can't be rebound, e.g. of the tabs or the column list. Some of them
+/// are handled before the keymap is checked, so an action bound to them would never run
+const RESERVED_KEYS: [KeyCode; 33] = [
+    KeyCode::Enter,
+    KeyCode::Esc,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::Char('t'),
+    KeyCode::Char('w'),
+    KeyCode::Char('{'),
+    KeyCode::Char('}'),
+    KeyCode::Char('m'),
+    KeyCode::Char('g'),
+    KeyCode::Char('G'),
+    KeyCode::Char('y'),
+    KeyCode::Char('Y'),
+    KeyCode::Char('Q'),
+    KeyCode::Char('0'),
+    KeyCode::Char('$'),
+    KeyCode::Char('o'),
+    KeyCode::Char('['),
+    KeyCode::Char(']'),
+    KeyCode::Char('c'),
+    KeyCode::Char('C'),
+    KeyCode::Char('f'),
+    KeyCode::Char('P'),
+    KeyCode::Char('s'),
+    KeyCode::Char('x'),
+    KeyCode::Char('T'),
+    KeyCode::Char('J'),
+    KeyCode::Char('K'),
+    KeyCode::Char('E'),
+];
+
+pub static KEYMAP_FILE: Lazy<String> = Lazy::new(|| {
+    Path::new(CONFIG_PATH.as_str())
+        .join("keymap.toml")
+        .to_str()
+        .unwrap()
+        .to_string()
+});
+
+/// Errors are reported on startup, the default keys are used if it fails to load later anyway
+pub static KEYMAP: Lazy<Keymap> = Lazy::new(|| Keymap::load().unwrap_or_default());
+
+/// Keys of the actions, e.g.
+/// ```toml
+/// move_left = "a"
+/// run_query = "F5"
+/// ```
+/// Actions missing in the keymap file keep their default keys
+pub struct Keymap {
+    actions: HashMap<KeyCode, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            actions: DEFAULT_KEYS
+                .into_iter()
+                .map(|(action, key)| (key, action))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn load() -> Result<Self> {
+        if !Path::new(KEYMAP_FILE.as_str()).exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(KEYMAP_FILE.as_str())?;
+        Self::parse(&content).with_context(|| format!("Failed to parse {}", *KEYMAP_FILE))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let bindings: HashMap<Action, String> = toml::from_str(content)?;
+        let mut keys = HashMap::from(DEFAULT_KEYS);
+        for (action, key) in bindings {
+            let key = parse_key(&key)?;
+            if RESERVED_KEYS.contains(&key) {
+                bail!(
+                    "{:?} can't be bound to {:?}, the key is used by another keybind",
+                    action,
+                    key
+                );
+            }
+            keys.insert(action, key);
+        }
+
+        let mut actions = HashMap::new();
+        for (action, key) in keys {
+            if let Some(other) = actions.insert(key, action) {
+                bail!(
+                    "{:?} and {:?} are bound to the same key {:?}",
+                    action,
+                    other,
+                    key
+                );
+            }
+        }
+
+        Ok(Self { actions })
+    }
+
+    pub fn get_action(&self, key: KeyCode) -> Option<Action> {
+        self.actions.get(&key).copied()
+    }
+
+    pub fn is_action(&self, key: KeyCode, action: Action) -> bool {
+        self.get_action(key) == Some(action)
+    }
+
+    /// Key of the action the way it's written in the keymap file, e.g. `i` or `F5`
+    pub fn get_key_name(&self, action: Action) -> String {
+        self.actions
+            .iter()
+            .find(|(_, bound)| **bound == action)
+            .map(|(key, _)| format_key(*key))
+            .unwrap_or_default()
+    }
+}
+
+/// Single character, or name of the key like `Enter` or `F5`
+fn parse_key(value: &str) -> Result<KeyCode> {
+    let mut chars = value.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(ch));
+    }
+
+    let key = match value {
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Tab" => KeyCode::Tab,
+        "Backspace" => KeyCode::Backspace,
+        "Delete" => KeyCode::Delete,
+        "Insert" => KeyCode::Insert,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        _ => match value.strip_prefix('F').and_then(|n| n.parse().ok()) {
+            Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+            _ => bail!("Unknown key '{}'", value),
+        },
+    };
+
+    Ok(key)
+}
+
+/// Reverse of `parse_key`, names of the other keys match their variants
+fn format_key(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(ch) => ch.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        key => format!("{:?}", key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_rebound() {
+        let keymap = Keymap::parse("move_left = \"a\"\nrun_query = \"F5\"").unwrap();
+
+        assert_eq!(
+            keymap.get_action(KeyCode::Char('a')),
+            Some(Action::MoveLeft)
+        );
+        assert_eq!(keymap.get_action(KeyCode::F(5)), Some(Action::RunQuery));
+        assert_eq!(keymap.get_action(KeyCode::Char('h')), None);
+        assert_eq!(keymap.get_action(KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(keymap.get_key_name(Action::RunQuery), "F5");
+        assert_eq!(keymap.get_key_name(Action::EditQuery), "i");
+    }
+
+    #[test]
+    fn conflicting_keys_are_rejected() {
+        assert!(Keymap::parse("quit = \"h\"").is_err());
+        assert!(Keymap::parse("quit = \"Space bar\"").is_err());
+    }
+
+    #[test]
+    fn reserved_keys_are_rejected() {
+        assert!(Keymap::parse("edit_query = \"t\"").is_err());
+        assert!(Keymap::parse("run_query = \"Enter\"").is_err());
+        assert!(Keymap::parse("quit = \"f\"").is_err());
+    }
+}
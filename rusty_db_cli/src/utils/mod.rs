@@ -1,2 +1,11 @@
+pub mod canonical_query;
+pub mod document_history;
 pub mod external_editor;
 pub mod fuzzy;
+pub mod keymap;
+pub mod password;
+pub mod pipeline_file;
+pub mod profiles;
+pub mod projection;
+pub mod query_history;
+pub mod theme;
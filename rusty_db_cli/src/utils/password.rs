@@ -0,0 +1,62 @@
+use std::io::{self, Write};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+
+/// Stands for the password in the connection uri, e.g. `mongodb://user:<password>@host`, so it
+/// doesn't end up in the shell history
+pub const PASSWORD_PLACEHOLDER: &str = "<password>";
+
+pub fn is_password_prompted(uri: &str) -> bool {
+    uri.contains(PASSWORD_PLACEHOLDER)
+}
+
+/// Reads the password from the terminal, typed characters are shown as `*`
+pub fn prompt_password(prompt: &str) -> io::Result<String> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+
+    enable_raw_mode()?;
+    let password = read_masked();
+    disable_raw_mode()?;
+    eprintln!();
+
+    password
+}
+
+fn read_masked() -> io::Result<String> {
+    let mut password = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Enter => return Ok(password),
+            KeyCode::Esc => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "Password prompt cancelled",
+                ))
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "Password prompt cancelled",
+                ))
+            }
+            KeyCode::Backspace if password.pop().is_some() => eprint!("\u{8} \u{8}"),
+            KeyCode::Char(ch) => {
+                password.push(ch);
+                eprint!("*");
+            }
+            _ => {}
+        }
+        io::stderr().flush()?;
+    }
+}
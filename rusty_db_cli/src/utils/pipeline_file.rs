@@ -0,0 +1,97 @@
+use std::fs;
+
+use anyhow::{bail, Context, Result};
+use rusty_db_cli_mongo::{interpreter::Interpreter, lexer::TokenType};
+
+/// Aggregation of the collection with the pipeline array stored in a `.json` or `.js` file,
+/// checked by the same parser as the queries written in the editor
+pub fn get_pipeline_query(path: &str, collection: &str) -> Result<String> {
+    let pipeline =
+        fs::read_to_string(path).with_context(|| format!("Failed to read file {}", path))?;
+    // Pipeline starts on its own line, so the lines of the query match the lines of the file
+    let query = format!(
+        "db.{}.aggregate(\n{}\n)",
+        collection,
+        pipeline.trim_end().trim_end_matches(';')
+    );
+
+    let interpreter = Interpreter::new().tokenize(query.clone());
+    let error = match interpreter.lexer_error {
+        Some(ref err) => Some((err.position, err.message.clone())),
+        None => interpreter
+            .try_parse()
+            .1
+            .map(|err| (err.token_pos, err.message)),
+    };
+    if let Some((token_pos, message)) = error {
+        match interpreter.tokens.get(token_pos) {
+            // Lines of the tokens are counted from zero, the first line of the query is not in
+            // the file though
+            Some(token) => bail!(
+                "{}:{}:{}: {}",
+                path,
+                token.line,
+                token.range.start + 1,
+                message
+            ),
+            None => bail!("{}: {}", path, message),
+        }
+    }
+
+    let is_array = interpreter
+        .tokens
+        .iter()
+        .skip_while(|token| token.r#type != TokenType::LeftParen)
+        .nth(1)
+        .is_some_and(|token| token.r#type == TokenType::LeftBracket);
+    if !is_array {
+        bail!(
+            "{} has to contain a pipeline array, e.g. [{{$match: {{}}}}]",
+            path
+        );
+    }
+
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn write_pipeline(name: &str, content: &str) -> String {
+        let path = env::temp_dir().join(name);
+        fs::write(&path, content).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn pipeline_is_wrapped_into_aggregate() {
+        let path = write_pipeline("rdbcli_pipeline.js", "[\n  {$match: {a: 1}}\n];\n");
+
+        assert_eq!(
+            get_pipeline_query(&path, "users").unwrap(),
+            "db.users.aggregate(\n[\n  {$match: {a: 1}}\n]\n)"
+        );
+    }
+
+    #[test]
+    fn parse_error_has_position_in_file() {
+        let path = write_pipeline("rdbcli_invalid_pipeline.js", "[\n  {$match: {a: }}\n]");
+
+        let message = get_pipeline_query(&path, "users").unwrap_err().to_string();
+        assert!(
+            message.starts_with(&format!("{}:2:", path)),
+            "Unexpected error: {}",
+            message
+        );
+    }
+
+    #[test]
+    fn pipeline_has_to_be_array() {
+        let path = write_pipeline("rdbcli_object_pipeline.json", "{\"$match\": {}}");
+
+        assert!(get_pipeline_query(&path, "users").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use super::external_editor::CONFIG_PATH;
+use crate::connectors::base::redact_uri;
+
+/// Connection stored under a name in the profiles file, e.g.
+/// ```toml
+/// [local]
+/// uri = "mongodb://localhost:27017"
+/// database = "test"
+/// ```
+#[derive(Deserialize, Clone)]
+pub struct ConnectionProfile {
+    pub uri: String,
+    /// Database to switch to after connecting, when it's not part of the uri
+    pub database: Option<String>,
+}
+
+impl fmt::Debug for ConnectionProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionProfile")
+            .field("uri", &redact_uri(&self.uri))
+            .field("database", &self.database)
+            .finish()
+    }
+}
+
+pub static PROFILES_FILE: Lazy<String> = Lazy::new(|| {
+    Path::new(CONFIG_PATH.as_str())
+        .join("profiles.toml")
+        .to_str()
+        .unwrap()
+        .to_string()
+});
+
+fn load_profiles() -> Result<BTreeMap<String, ConnectionProfile>> {
+    if !Path::new(PROFILES_FILE.as_str()).exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let content = fs::read_to_string(PROFILES_FILE.as_str())?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", *PROFILES_FILE))
+}
+
+/// Names of the stored profiles, empty when the profiles file can't be read
+pub fn get_profile_names() -> Vec<String> {
+    load_profiles()
+        .map(|profiles| profiles.into_keys().collect())
+        .unwrap_or_default()
+}
+
+/// Finds profile with the given name, argument that is not a profile name is used as uri
+pub fn resolve_connection(value: &str) -> Result<ConnectionProfile> {
+    let profiles = load_profiles()?;
+    if let Some(profile) = profiles.get(value) {
+        return Ok(profile.clone());
+    }
+
+    if value.contains("://") {
+        return Ok(ConnectionProfile {
+            uri: value.to_string(),
+            database: None,
+        });
+    }
+
+    Err(anyhow!(
+        "Profile '{}' not found, available profiles: {}",
+        value,
+        if profiles.is_empty() {
+            String::from("none")
+        } else {
+            profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+        }
+    ))
+}
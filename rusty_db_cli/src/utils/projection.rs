@@ -0,0 +1,207 @@
+use anyhow::{anyhow, bail, Result};
+use rusty_db_cli_mongo::{
+    lexer::{Lexer, Token, TokenType},
+    parser::Parser,
+    types::literals::Literal,
+};
+
+/// Rewrites the projection of the find query to include only the given columns, e.g.
+/// `db.users.find({}, {a: 1, b: 1, _id: 0})`. Rest of the query is kept as it was written
+pub fn set_find_projection(query: &str, columns: &[String]) -> Result<String> {
+    if columns.is_empty() {
+        bail!("No columns to project");
+    }
+
+    let tokens = parse_tokens(query)?;
+    let Some(open_paren) = get_find_paren(&tokens) else {
+        bail!("Only db.<collection>.find() queries can be projected");
+    };
+    let (args, close_paren) = get_arguments(&tokens, open_paren)?;
+
+    let projection = get_projection(columns);
+    let lines = query.split('\n').collect::<Vec<_>>();
+    let position = |token: &Token, column: usize| get_char_position(&lines, token.line, column);
+    let (start, end, value) = match args.as_slice() {
+        [] => {
+            let start = position(&tokens[close_paren], tokens[close_paren].range.start);
+            (start, start, format!("{{}}, {}", projection))
+        }
+        [(_, filter_end)] => {
+            let end = position(&tokens[*filter_end], tokens[*filter_end].range.end + 1);
+            (end, end, format!(", {}", projection))
+        }
+        [_, (projection_start, projection_end), ..] => (
+            position(
+                &tokens[*projection_start],
+                tokens[*projection_start].range.start,
+            ),
+            position(
+                &tokens[*projection_end],
+                tokens[*projection_end].range.end + 1,
+            ),
+            projection,
+        ),
+    };
+
+    let chars = query.chars().collect::<Vec<_>>();
+    let projected_query = format!(
+        "{}{}{}",
+        chars[..start].iter().collect::<String>(),
+        value,
+        chars[end..].iter().collect::<String>()
+    );
+    parse_tokens(&projected_query)?;
+
+    Ok(projected_query)
+}
+
+/// Tokens of the query, which has to be parsed without errors as well
+pub fn parse_tokens(query: &str) -> Result<Vec<Token>> {
+    let tokens = Lexer::new(query.to_string())
+        .scan_tokens()
+        .map_err(|(_, errors)| anyhow!("Query can't be parsed: {}", errors[0].message))?;
+    Parser::new(tokens.clone())
+        .parse()
+        .map_err(|err| anyhow!("Query can't be parsed: {}", err.message))?;
+
+    Ok(tokens)
+}
+
+/// Position of the `(` of `db.<collection>.find(`
+fn get_find_paren(tokens: &[Token]) -> Option<usize> {
+    match tokens {
+        [Token {
+            literal: Some(Literal::String(db)),
+            ..
+        }, Token {
+            r#type: TokenType::Dot,
+            ..
+        }, Token {
+            r#type: TokenType::Identifier,
+            ..
+        }, Token {
+            r#type: TokenType::Dot,
+            ..
+        }, Token {
+            literal: Some(Literal::String(command)),
+            ..
+        }, Token {
+            r#type: TokenType::LeftParen,
+            ..
+        }, ..]
+            if db == "db" && command == "find" =>
+        {
+            Some(5)
+        }
+        _ => None,
+    }
+}
+
+/// First and last token of each argument of the call, with the position of its `)`
+fn get_arguments(tokens: &[Token], open_paren: usize) -> Result<(Vec<(usize, usize)>, usize)> {
+    let mut args = Vec::new();
+    let mut arg_start = None;
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate().skip(open_paren + 1) {
+        match token.r#type {
+            TokenType::RightParen if depth == 0 => {
+                if let Some(start) = arg_start {
+                    args.push((start, idx - 1));
+                }
+                return Ok((args, idx));
+            }
+            TokenType::Comma if depth == 0 => {
+                if let Some(start) = arg_start.take() {
+                    args.push((start, idx - 1));
+                }
+                continue;
+            }
+            TokenType::LeftParen | TokenType::LeftBrace | TokenType::LeftBracket => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace | TokenType::RightBracket => depth -= 1,
+            _ => {}
+        }
+        arg_start.get_or_insert(idx);
+    }
+
+    bail!("Missing ) of the find call")
+}
+
+/// `_id` is returned unless excluded, so it's hidden when it's not one of the columns
+fn get_projection(columns: &[String]) -> String {
+    let mut fields = columns
+        .iter()
+        .map(|column| format!("{}: 1", get_key(column)))
+        .collect::<Vec<_>>();
+    if !columns.iter().any(|column| column == "_id") {
+        fields.push(String::from("_id: 0"));
+    }
+
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// Keys that aren't valid identifiers are quoted
+fn get_key(column: &str) -> String {
+    let is_identifier = column
+        .chars()
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '$' || ch == '_')
+        && column
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || ch == '$' || ch == '_');
+    if is_identifier {
+        column.to_string()
+    } else {
+        serde_json::Value::String(column.to_string()).to_string()
+    }
+}
+
+/// Lexer positions are character columns within a line
+fn get_char_position(lines: &[&str], line: usize, column: usize) -> usize {
+    lines[..line]
+        .iter()
+        .map(|line| line.chars().count() + 1)
+        .sum::<usize>()
+        + column
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn projection_is_added() {
+        assert_eq!(
+            set_find_projection("db.users.find()", &columns(&["a", "b"])).unwrap(),
+            "db.users.find({}, {a: 1, b: 1, _id: 0})"
+        );
+        assert_eq!(
+            set_find_projection(
+                "db.users.find({age: {$gt: 1}}).limit(5)",
+                &columns(&["_id"])
+            )
+            .unwrap(),
+            "db.users.find({age: {$gt: 1}}, {_id: 1}).limit(5)"
+        );
+    }
+
+    #[test]
+    fn projection_is_replaced() {
+        assert_eq!(
+            set_find_projection(
+                "db.users.find(\n  {a: 1},\n  {b: 0}\n)",
+                &columns(&["a", "first name"])
+            )
+            .unwrap(),
+            "db.users.find(\n  {a: 1},\n  {a: 1, \"first name\": 1, _id: 0}\n)"
+        );
+    }
+
+    #[test]
+    fn only_find_is_projected() {
+        assert!(set_find_projection("db.users.aggregate([])", &columns(&["a"])).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use std::fs;
+
+use anyhow::Result;
+
+use super::external_editor::{get_query_history_file, write_file_atomically};
+
+/// Amount of queries kept in the history of a connection
+const MAX_QUERY_HISTORY: usize = 100;
+
+/// Queries run on a connection, stored as a JSON array as the queries span multiple lines
+pub struct QueryHistory {
+    file: String,
+    /// Oldest query first
+    queries: Vec<String>,
+}
+
+impl QueryHistory {
+    pub fn load(host: &str, database: &str) -> Result<Self> {
+        let file = get_query_history_file(host, database);
+        let content = fs::read_to_string(&file)?;
+        let queries = if content.trim().is_empty() {
+            Vec::new()
+        } else {
+            serde_json::from_str(&content)?
+        };
+
+        Ok(Self { file, queries })
+    }
+
+    /// Adds query as the newest one, running the same query again only moves it
+    pub fn push(&mut self, query: &str) -> Result<()> {
+        self.queries.retain(|value| value != query);
+        self.queries.push(query.to_string());
+        if self.queries.len() > MAX_QUERY_HISTORY {
+            self.queries.remove(0);
+        }
+
+        write_file_atomically(&self.file, &serde_json::to_string(&self.queries)?)
+    }
+
+    /// Query `age` steps back in the history, 0 is the newest one
+    pub fn get(&self, age: usize) -> Option<&String> {
+        self.queries.iter().rev().nth(age)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+}
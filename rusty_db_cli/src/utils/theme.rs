@@ -0,0 +1,137 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+use super::external_editor::CONFIG_PATH;
+
+pub static THEME_FILE: Lazy<String> = Lazy::new(|| {
+    Path::new(CONFIG_PATH.as_str())
+        .join("theme.toml")
+        .to_str()
+        .unwrap()
+        .to_string()
+});
+
+/// Errors are reported on startup, the default colors are used if it fails to load later anyway
+pub static THEME: Lazy<Theme> = Lazy::new(|| Theme::load().unwrap_or_default());
+
+/// Colors of the theme file, e.g.
+/// ```toml
+/// selection_fg = "white"
+/// selection_bg = "#005f87"
+/// header_bold = true
+/// ```
+/// Values are color names, `#rrggbb` or indexes of the 256 terminal colors. Missing values keep
+/// their defaults
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields, default)]
+struct ThemeConfig {
+    selection_fg: Option<String>,
+    selection_bg: Option<String>,
+    header_fg: Option<String>,
+    header_bg: Option<String>,
+    header_bold: bool,
+    error_fg: Option<String>,
+    info_fg: Option<String>,
+    normal_fg: Option<String>,
+}
+
+pub struct Theme {
+    /// Selected row of the table and selected items of lists
+    pub selection: Style,
+    /// Column names of the table
+    pub header: Style,
+    pub error_message: Style,
+    pub info_message: Style,
+    pub normal_message: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            selection: Style::default().bg(Color::Yellow).fg(Color::Black),
+            header: Style::default(),
+            error_message: Style::default().fg(Color::Red),
+            info_message: Style::default(),
+            normal_message: Style::default(),
+        }
+    }
+}
+
+impl Theme {
+    pub fn load() -> Result<Self> {
+        if !Path::new(THEME_FILE.as_str()).exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(THEME_FILE.as_str())?;
+        Self::parse(&content).with_context(|| format!("Failed to parse {}", *THEME_FILE))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let config: ThemeConfig = toml::from_str(content)?;
+        let mut theme = Self::default();
+
+        if let Some(color) = parse_color("selection_fg", &config.selection_fg)? {
+            theme.selection = theme.selection.fg(color);
+        }
+        if let Some(color) = parse_color("selection_bg", &config.selection_bg)? {
+            theme.selection = theme.selection.bg(color);
+        }
+        if let Some(color) = parse_color("header_fg", &config.header_fg)? {
+            theme.header = theme.header.fg(color);
+        }
+        if let Some(color) = parse_color("header_bg", &config.header_bg)? {
+            theme.header = theme.header.bg(color);
+        }
+        if config.header_bold {
+            theme.header = theme.header.add_modifier(Modifier::BOLD);
+        }
+        if let Some(color) = parse_color("error_fg", &config.error_fg)? {
+            theme.error_message = theme.error_message.fg(color);
+        }
+        if let Some(color) = parse_color("info_fg", &config.info_fg)? {
+            theme.info_message = theme.info_message.fg(color);
+        }
+        if let Some(color) = parse_color("normal_fg", &config.normal_fg)? {
+            theme.normal_message = theme.normal_message.fg(color);
+        }
+
+        Ok(theme)
+    }
+}
+
+fn parse_color(key: &str, value: &Option<String>) -> Result<Option<Color>> {
+    value
+        .as_deref()
+        .map(|value| {
+            Color::from_str(value).map_err(|_| anyhow!("Unknown color '{}' of {}", value, key))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_overridden() {
+        let theme = Theme::parse("selection_bg = \"#005f87\"\nheader_bold = true").unwrap();
+
+        assert_eq!(
+            theme.selection,
+            Style::default().bg(Color::Rgb(0, 95, 135)).fg(Color::Black)
+        );
+        assert_eq!(theme.header, Style::default().add_modifier(Modifier::BOLD));
+        assert_eq!(theme.error_message, Style::default().fg(Color::Red));
+    }
+
+    #[test]
+    fn unknown_color_is_rejected() {
+        assert!(Theme::parse("error_fg = \"reddish\"").is_err());
+        assert!(Theme::parse("border_fg = \"red\"").is_err());
+    }
+}
@@ -3,9 +3,10 @@ use std::{cmp, u16};
 use ratatui::{
     prelude::{Buffer, Rect},
     style::{Color, Style},
-    text::Text,
+    text::{Line, Span, Text},
     widgets::{Block, StatefulWidget, Widget},
 };
+use unicode_width::UnicodeWidthChar;
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
 pub struct Cell<'a> {
@@ -58,6 +59,9 @@ pub struct ScrollableTable<'a> {
     rows: Vec<Row<'a>>,
     block: Block<'a>,
     header: Row<'a>,
+    /// Style of the selected row
+    highlight_style: Style,
+    header_style: Style,
 }
 
 pub struct ScrollableTableState {
@@ -101,8 +105,24 @@ impl<'a> ScrollableTable<'a> {
             rows,
             block: Block::default(),
             header,
+            highlight_style: get_default_highlight_style(),
+            header_style: Style::default(),
         }
     }
+
+    pub fn highlight_style(mut self, style: Style) -> Self {
+        self.highlight_style = style;
+        self
+    }
+
+    pub fn header_style(mut self, style: Style) -> Self {
+        self.header_style = style;
+        self
+    }
+}
+
+fn get_default_highlight_style() -> Style {
+    Style::default().bg(Color::Yellow).fg(Color::Black)
 }
 
 impl<'a> Default for ScrollableTable<'a> {
@@ -111,6 +131,8 @@ impl<'a> Default for ScrollableTable<'a> {
             rows: Vec::new(),
             block: Block::default(),
             header: Row::default(),
+            highlight_style: get_default_highlight_style(),
+            header_style: Style::default(),
         }
     }
 }
@@ -144,39 +166,40 @@ impl<'a> StatefulWidget for ScrollableTable<'a> {
 
         buf.set_style(area, Style::default());
 
-        render_row(
-            &self.header,
-            Rect {
-                x: 0,
-                y: 0,
-                width: table_area.right(),
-                height: 1,
-            },
-            buf,
-            state,
-        );
+        // Rows are drawn relative to the table area, so its screen rows map to the table rows
+        let header_area = Rect {
+            height: 1,
+            ..table_area
+        };
+        render_row(&self.header, header_area, buf, state, Style::default());
+        buf.set_style(header_area, self.header_style);
         for (i, table_row) in self
             .rows
             .iter_mut()
             .skip(state.vertical_offset)
             .enumerate()
-            .take(area.bottom() as usize - 1)
+            .take(table_area.height.saturating_sub(1) as usize)
         {
             let table_row_area = Rect {
-                x: 0,
-                y: (i + 1) as u16,
-                width: table_area.right(),
+                y: table_area.y + (i + 1) as u16,
                 height: table_row.total_height(),
+                ..table_area
+            };
+            let style = match state.vertical_select == i + 1 {
+                true => self.highlight_style,
+                false => Style::default(),
             };
-            render_row(table_row, table_row_area, buf, state)
+            render_row(table_row, table_row_area, buf, state, style)
         }
     }
 }
-fn render_row(row: &Row<'_>, area: Rect, buf: &mut Buffer, state: &ScrollableTableState) {
-    let style = match state.vertical_select > 0 && area.y as usize == state.vertical_select {
-        true => Style::default().bg(Color::Yellow).fg(Color::Black),
-        false => Style::default(),
-    };
+fn render_row(
+    row: &Row<'_>,
+    area: Rect,
+    buf: &mut Buffer,
+    state: &ScrollableTableState,
+    style: Style,
+) {
     buf.set_style(area, style);
 
     if state.cell_widths.is_empty() {
@@ -185,29 +208,67 @@ fn render_row(row: &Row<'_>, area: Rect, buf: &mut Buffer, state: &ScrollableTab
 
     let mut width_occupied: u16 = 0;
     for (x, cell) in row.cells.iter().enumerate().skip(state.horizontal_offset) {
-        let cell_width = cmp::min(
-            state.cell_widths[x].saturating_add(1),
-            area.width.checked_sub(width_occupied).unwrap_or(area.width),
-        );
-        width_occupied += cell_width;
-
-        if width_occupied > area.width {
+        // Last column is as wide as the table, so it takes the rest of the row
+        let Some(width) = state.cell_widths.get(x) else {
+            break;
+        };
+        let remaining_width = area.width.saturating_sub(width_occupied);
+        if remaining_width == 0 {
             break;
         }
+        let cell_width = cmp::min(width.saturating_add(1), remaining_width);
+        width_occupied += cell_width;
 
         for (i, line) in cell.content.lines.iter().enumerate() {
             let area = Rect {
-                x: width_occupied - cell_width,
+                x: area.x + width_occupied - cell_width,
                 y: area.y + i as u16,
                 width: cell_width.saturating_sub(1),
                 height: row.total_height(),
             };
             buf.set_style(area, cell.style);
-            buf.set_line(area.x, area.y + i as u16, line, area.width);
+            buf.set_line(
+                area.x,
+                area.y + i as u16,
+                &truncate_line(line, area.width),
+                area.width,
+            );
         }
     }
 }
 
+/// Cuts the line to fit into the width, the cut is marked with an ellipsis
+fn truncate_line<'a>(line: &Line<'a>, width: u16) -> Line<'a> {
+    if line.width() <= width as usize {
+        return line.clone();
+    }
+
+    let mut remaining = (width as usize).saturating_sub(1);
+    let mut spans = Vec::new();
+    for span in line.spans.iter() {
+        let content = span
+            .content
+            .chars()
+            .take_while(|c| match remaining.checked_sub(c.width().unwrap_or(0)) {
+                Some(value) => {
+                    remaining = value;
+                    true
+                }
+                None => {
+                    remaining = 0;
+                    false
+                }
+            })
+            .collect::<String>();
+        spans.push(Span::styled(content, span.style));
+    }
+    if width > 0 {
+        spans.push(Span::raw("…"));
+    }
+
+    Line::from(spans)
+}
+
 impl<'a> Widget for ScrollableTable<'a> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
         let mut state = ScrollableTableState::default();
@@ -17,7 +17,7 @@ pub fn try_from_variant(args: TokenStream) -> TokenStream {
             if variant.fields.is_empty() {
                 continue;
             }
-            let first_variant = variant.fields.iter().next().unwrap();
+            let first_variant = &variant.fields.iter().next().unwrap().ty;
 
             try_from_fc.extend(quote! {
                 impl TryFrom<#enum_name> for #first_variant {
@@ -33,8 +33,30 @@ pub fn try_from_variant(args: TokenStream) -> TokenStream {
                 }
             });
         }
+    } else if let syn::Data::Struct(data_struct) = &input.data {
+        if data_struct.fields.len() != 1 {
+            return syn::Error::new_spanned(input, "Expected a struct with exactly one field")
+                .to_compile_error()
+                .into();
+        }
+        let field = data_struct.fields.iter().next().unwrap();
+        let field_type = &field.ty;
+        let accessor = match &field.ident {
+            Some(ident) => quote! { #ident },
+            None => quote! { 0 },
+        };
+
+        try_from_fc.extend(quote! {
+            impl TryFrom<#enum_name> for #field_type {
+                type Error = ();
+
+                fn try_from(value: #enum_name) -> Result<#field_type, Self::Error> {
+                    Ok(value.#accessor)
+                }
+            }
+        });
     } else {
-        return syn::Error::new_spanned(input, "Expected an enum")
+        return syn::Error::new_spanned(input, "Expected an enum or a struct")
             .to_compile_error()
             .into();
     }
@@ -52,8 +74,20 @@ pub fn typed_for_enum(args: TokenStream) -> TokenStream {
     if let syn::Data::Enum(data_enum) = &input.data {
         let info = data_enum.variants.iter().map(|variant| {
             let variant_name = &variant.ident;
-            quote! {
-                #enum_name::#variant_name(val) => val.get_type_info(),
+            // Variants with more fields take the type of the first one
+            match &variant.fields {
+                syn::Fields::Unit => quote! {
+                    #enum_name::#variant_name => TypeInfo::default(),
+                },
+                syn::Fields::Unnamed(_) => quote! {
+                    #enum_name::#variant_name(val, ..) => val.get_type_info(),
+                },
+                syn::Fields::Named(fields) => {
+                    let first_field = &fields.named.first().unwrap().ident;
+                    quote! {
+                        #enum_name::#variant_name { #first_field, .. } => #first_field.get_type_info(),
+                    }
+                }
             }
         });
 
@@ -0,0 +1,43 @@
+use rusty_db_cli_derive_internals::TryFrom;
+
+#[derive(TryFrom)]
+struct Wrapper(String);
+
+#[derive(TryFrom)]
+struct Named {
+    inner: Vec<u8>,
+}
+
+#[derive(TryFrom, Debug)]
+enum Value {
+    Text(String),
+    Number(i64),
+    Empty,
+}
+
+#[test]
+fn tuple_struct() {
+    assert_eq!(
+        String::try_from(Wrapper("value".to_string())),
+        Ok("value".to_string())
+    );
+}
+
+#[test]
+fn named_struct() {
+    assert_eq!(
+        Vec::<u8>::try_from(Named { inner: vec![1, 2] }),
+        Ok(vec![1, 2])
+    );
+}
+
+#[test]
+fn enum_variants() {
+    assert_eq!(
+        String::try_from(Value::Text("value".to_string())),
+        Ok("value".to_string())
+    );
+    assert_eq!(i64::try_from(Value::Number(1)), Ok(1));
+    assert_eq!(i64::try_from(Value::Text("value".to_string())), Err(()));
+    assert_eq!(String::try_from(Value::Empty), Err(()));
+}
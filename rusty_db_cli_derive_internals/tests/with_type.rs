@@ -0,0 +1,49 @@
+use rusty_db_cli_derive_internals::WithType;
+
+#[derive(Default, Debug, PartialEq)]
+struct TypeInfo {
+    name: String,
+}
+
+trait Typed {
+    fn get_type_info(&self) -> TypeInfo;
+}
+
+struct Text;
+
+impl Typed for Text {
+    fn get_type_info(&self) -> TypeInfo {
+        TypeInfo {
+            name: "Text".to_string(),
+        }
+    }
+}
+
+// Fields other than the first one are only there to be skipped by the derive
+#[allow(dead_code)]
+#[derive(WithType)]
+enum Value {
+    Text(Text),
+    Pair(Text, i64),
+    Named { text: Text, size: i64 },
+    Empty,
+}
+
+#[test]
+fn variants() {
+    let text = TypeInfo {
+        name: "Text".to_string(),
+    };
+
+    assert_eq!(Value::Text(Text).get_type_info(), text);
+    assert_eq!(Value::Pair(Text, 1).get_type_info(), text);
+    assert_eq!(
+        Value::Named {
+            text: Text,
+            size: 1
+        }
+        .get_type_info(),
+        text
+    );
+    assert_eq!(Value::Empty.get_type_info(), TypeInfo::default());
+}
@@ -0,0 +1,144 @@
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+use rusty_db_cli_mongo::{
+    lexer::{Token, TokenType},
+    standard_library::{COMMANDS, SUB_COMMANDS},
+    types::literals::Literal,
+};
+use sublime_fuzzy::best_match;
+
+/// Largest edit distance at which a name is still considered a typo of a known command
+const MAX_TYPO_DISTANCE: usize = 2;
+
+/// Checks names of the collection methods (and methods chained after them) in an already
+/// parsed program, e.g. `db.users.fnid({})`
+pub fn check_commands(tokens: &[Token]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut pos = 0;
+
+    while pos < tokens.len() {
+        let is_command = identifier_at(tokens, pos) == Some("db")
+            && is_type_at(tokens, pos + 1, TokenType::Dot)
+            && identifier_at(tokens, pos + 2).is_some()
+            && is_type_at(tokens, pos + 3, TokenType::Dot)
+            && is_type_at(tokens, pos + 5, TokenType::LeftParen);
+
+        let Some(name) = identifier_at(tokens, pos + 4).filter(|_| is_command) else {
+            pos += 1;
+            continue;
+        };
+
+        diagnostics.extend(check_name(&tokens[pos + 4], name, COMMANDS));
+        pos = skip_parameters(tokens, pos + 5);
+
+        while is_type_at(tokens, pos, TokenType::Dot)
+            && is_type_at(tokens, pos + 2, TokenType::LeftParen)
+        {
+            let Some(name) = identifier_at(tokens, pos + 1) else {
+                break;
+            };
+            diagnostics.extend(check_name(&tokens[pos + 1], name, SUB_COMMANDS));
+            pos = skip_parameters(tokens, pos + 2);
+        }
+    }
+
+    diagnostics
+}
+
+fn check_name(token: &Token, name: &str, known: &[&str]) -> Option<Diagnostic> {
+    // Method names are matched case insensitively by the connector
+    if known.iter().any(|value| value.eq_ignore_ascii_case(name)) {
+        return None;
+    }
+
+    let message = match suggest(name, known) {
+        Some(suggestion) => format!("Unknown command '{}', did you mean '{}'?", name, suggestion),
+        None => format!("Unknown command '{}'", name),
+    };
+
+    Some(Diagnostic {
+        severity: Some(DiagnosticSeverity::WARNING),
+        range: Range {
+            start: Position::new(token.line as u32, token.range.start as u32),
+            // Token ranges are inclusive, LSP ranges are not
+            end: Position::new(token.line as u32, token.range.end as u32 + 1),
+        },
+        message,
+        ..Default::default()
+    })
+}
+
+/// Prefers the best fuzzy match (abbreviations such as `agg`), fuzzy matching requires the
+/// characters to be in order though, so typos such as `fnid` fall back to edit distance
+fn suggest<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .filter_map(|value| best_match(name, value).map(|found| (found.score(), *value)))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, value)| value)
+        .or_else(|| {
+            known
+                .iter()
+                .map(|value| (edit_distance(name, value), *value))
+                .filter(|(distance, _)| *distance <= MAX_TYPO_DISTANCE)
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(_, value)| value)
+        })
+}
+
+/// Levenshtein distance where swapping two adjacent characters counts as a single edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut distances: Vec<Vec<usize>> = (0..=a.len())
+        .map(|i| (0..=b.len()).map(|j| if i == 0 { j } else { i }).collect())
+        .collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+fn identifier_at(tokens: &[Token], pos: usize) -> Option<&str> {
+    match tokens.get(pos) {
+        Some(Token {
+            r#type: TokenType::Identifier,
+            literal: Some(Literal::String(name)),
+            ..
+        }) => Some(name),
+        _ => None,
+    }
+}
+
+fn is_type_at(tokens: &[Token], pos: usize, token_type: TokenType) -> bool {
+    tokens
+        .get(pos)
+        .is_some_and(|token| token.r#type == token_type)
+}
+
+/// Returns position right after the parenthesis closing the one at `open`
+fn skip_parameters(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0;
+    for (pos, token) in tokens.iter().enumerate().skip(open) {
+        match token.r#type {
+            TokenType::LeftParen => depth += 1,
+            TokenType::RightParen => depth -= 1,
+            _ => (),
+        }
+        if depth == 0 {
+            return pos + 1;
+        }
+    }
+
+    tokens.len()
+}
@@ -5,6 +5,7 @@ use std::{
     path::Path,
 };
 
+use diagnostics::check_commands;
 use lsp_server::{Connection, ExtractError, Notification, Request, RequestId, Response};
 use lsp_types::{
     notification::{DidChangeTextDocument, DidOpenTextDocument},
@@ -12,13 +13,15 @@ use lsp_types::{
     CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse, Diagnostic,
     DiagnosticServerCapabilities, DiagnosticSeverity, DidChangeTextDocumentParams,
     DidOpenTextDocumentParams, InlayHintServerCapabilities, Position, PublishDiagnosticsParams,
-    Range, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    Range, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
 };
 use rusty_db_cli_mongo::{
     interpreter::Interpreter, parser::ParseError, standard_library::StandardLibrary,
     types::expressions::Node,
 };
 
+mod diagnostics;
+
 fn main() {
     let (connection, _) = Connection::stdio();
 
@@ -37,24 +40,9 @@ fn main() {
     })
     .unwrap();
 
-    let path = Path::new(get_config_path().as_str()).join(".collections.txt");
-
-    if !path.exists() {
-        File::create(path.clone()).expect("Failed to create collections file");
-    }
-
-    let mut file = File::open(path).unwrap();
-    let mut content = String::new();
-    file.read_to_string(&mut content).unwrap();
-    let collections = content
-        .split('\n')
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
-
     let _ = connection.initialize(server_capabilities).unwrap();
 
     let mut handler = Handler {
-        collections,
         cache: Cache::default(),
         lib: StandardLibrary::new(),
     };
@@ -96,7 +84,6 @@ struct Cache {
 }
 
 struct Handler {
-    collections: Vec<String>,
     cache: Cache,
     lib: StandardLibrary,
 }
@@ -104,7 +91,8 @@ struct Handler {
 impl Handler {
     fn handle_completion(&self, (params, id): (CompletionParams, RequestId)) -> Option<Response> {
         let _character = params.text_document_position.position.character;
-        let file_uri = params.text_document_position.text_document.uri.to_string();
+        let uri = params.text_document_position.text_document.uri;
+        let file_uri = uri.to_string();
 
         let mut debug_file = File::create("/home/janv/debug-compe.log").unwrap();
 
@@ -141,17 +129,17 @@ impl Handler {
             )
             .unwrap();
 
-        //let items: Vec<CompletionItem> = self
-        //    .collections
-        //    .clone()
-        //    .into_iter()
-        //    .map(|coll| CompletionItem {
-        //        label: coll.to_string(),
-        //        kind: Some(CompletionItemKind::VARIABLE),
-        //        detail: Some("Collection".to_owned()),
-        //        ..CompletionItem::default()
-        //    })
-        //    .collect();
+        // Read on every completion, so collections refreshed while the file is open are offered
+        items.extend(
+            load_collections(&uri)
+                .into_iter()
+                .map(|coll| CompletionItem {
+                    label: coll,
+                    kind: Some(CompletionItemKind::VARIABLE),
+                    detail: Some("Collection".to_owned()),
+                    ..CompletionItem::default()
+                }),
+        );
 
         Some(lsp_server::Response {
             id,
@@ -212,6 +200,7 @@ impl Cache {
         if let Some(err) = interpreter.lexer_error {
             error = Some(ParseError {
                 token_pos: err.position,
+                start_token_pos: None,
                 message: err.message,
                 r#type: err.token_error,
             })
@@ -222,8 +211,12 @@ impl Cache {
 
         let mut debug_file = File::create("/home/janv/debug.log").unwrap();
 
-        if let Some(err) = error {
+        let diagnostics = if let Some(err) = error {
             let token = &interpreter.tokens.get(err.token_pos).unwrap();
+            let start_token = err
+                .start_token_pos
+                .and_then(|pos| interpreter.tokens.get(pos))
+                .unwrap_or(token);
             debug_file
                 .write_all(
                     format!(
@@ -233,39 +226,33 @@ impl Cache {
                     .as_bytes(),
                 )
                 .unwrap();
-            Some(lsp_server::Notification {
-                method: "textDocument/publishDiagnostics".to_string(),
-                params: serde_json::to_value(PublishDiagnosticsParams {
-                    uri: params.text_document.uri,
-                    diagnostics: vec![Diagnostic {
-                        severity: Some(DiagnosticSeverity::ERROR),
-                        range: Range {
-                            start: Position::new(token.line as u32, token.range.start as u32),
-                            end: Position::new(token.line as u32, token.range.end as u32),
-                        },
-                        message: err.message,
-                        ..Default::default()
-                    }],
-                    version: None,
-                })
-                .ok()
-                .into(),
-            })
+            vec![Diagnostic {
+                severity: Some(DiagnosticSeverity::ERROR),
+                range: Range {
+                    start: Position::new(start_token.line as u32, start_token.range.start as u32),
+                    // Token ranges are inclusive, LSP ranges are not
+                    end: Position::new(token.line as u32, token.range.end as u32 + 1),
+                },
+                message: err.message,
+                ..Default::default()
+            }]
         } else {
             debug_file
                 .write_all("does not have error".as_bytes())
                 .unwrap();
-            Some(lsp_server::Notification {
-                method: "textDocument/publishDiagnostics".to_string(),
-                params: serde_json::to_value(PublishDiagnosticsParams {
-                    uri: params.text_document.uri,
-                    diagnostics: vec![],
-                    version: None,
-                })
-                .ok()
-                .into(),
+            check_commands(&interpreter.tokens)
+        };
+
+        Some(lsp_server::Notification {
+            method: "textDocument/publishDiagnostics".to_string(),
+            params: serde_json::to_value(PublishDiagnosticsParams {
+                uri: params.text_document.uri,
+                diagnostics,
+                version: None,
             })
-        }
+            .ok()
+            .into(),
+        })
     }
 
     pub fn on_open(&mut self, params: DidOpenTextDocumentParams) -> Option<Notification> {
@@ -295,6 +282,29 @@ where
     req.extract(R::METHOD)
 }
 
+/// Query file of a connection has its collections listed next to it, e.g.
+/// `.localhost_27017_shop.collections.txt`, other files get the last listed ones
+fn load_collections(uri: &Url) -> Vec<String> {
+    let connection_path = uri
+        .to_file_path()
+        .ok()
+        .map(|path| path.with_extension("collections.txt"))
+        .filter(|path| path.exists());
+    let path = connection_path
+        .unwrap_or_else(|| Path::new(get_config_path().as_str()).join(".collections.txt"));
+
+    let mut content = String::new();
+    if let Ok(mut file) = File::open(path) {
+        file.read_to_string(&mut content).unwrap_or_default();
+    }
+
+    content
+        .split('\n')
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .collect()
+}
+
 fn get_config_path() -> String {
     let home = home::home_dir().expect("HomeDir to be available");
 
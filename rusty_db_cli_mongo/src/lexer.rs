@@ -56,6 +56,14 @@ pub struct Token {
     pub range: Range,
 }
 
+impl Token {
+    /// Text of the token as it was written
+    pub fn lexeme(&self) -> &str {
+        &self.lexeme
+    }
+}
+
+/// Character (not byte) columns of a token within its line, both ends inclusive.
 #[derive(Debug, Clone)]
 pub struct Range {
     pub start: usize,
@@ -141,6 +149,11 @@ impl Lexer {
             '}' => self.add_token(TokenType::RightBrace),
             '[' => self.add_token(TokenType::LeftBracket),
             ']' => self.add_token(TokenType::RightBracket),
+            // Leading dot float, e.g. `.5`, unless it's a member access
+            '.' if self.peek().is_ascii_digit() && !self.is_after_operand() => match self.digit() {
+                Ok(_) => self.add_token(TokenType::Number),
+                Err(_) => self.add_token(TokenType::Unknown),
+            },
             '.' => self.add_token(TokenType::Dot),
             ',' => self.add_token(TokenType::Comma),
             ':' => self.add_token(TokenType::Colon),
@@ -150,21 +163,24 @@ impl Lexer {
                 }
                 Err(()) => self.add_token(TokenType::Unknown),
             },
-            '/' => {
-                match self.regex() {
-                    Ok(_) => {
-                        self.add_token(TokenType::Regex);
-                        self.start = self.current;
+            '/' => match self.regex() {
+                Ok(_) => {
+                    self.add_token(TokenType::Regex);
+                    self.start = self.current;
+                    self.start_relative = self.current_relative;
+                    self.current_string = String::new();
+
+                    // Flags are optional, the token is only emitted when there are some
+                    match self.regex_flags() {
+                        Ok(_) if self.current_string.is_empty() => {}
+                        Ok(_) => self.add_token(TokenType::RegexFlags),
+                        Err(_) => self.add_token(TokenType::Unknown),
                     }
-                    Err(_) => self.add_token(TokenType::Unknown),
-                }
-                match self.regex_flags() {
-                    Ok(_) => self.add_token(TokenType::RegexFlags),
-                    Err(_) => self.add_token(TokenType::Unknown),
                 }
-            }
+                Err(_) => self.add_token(TokenType::Unknown),
+            },
             _ => {
-                if c.is_ascii_digit() || (c == '-' && self.peek().is_numeric()) {
+                if c.is_ascii_digit() || (matches!(c, '-' | '+') && self.is_number_next()) {
                     match self.digit() {
                         Ok(_) => {
                             self.add_token(TokenType::Number);
@@ -205,6 +221,7 @@ impl Lexer {
         let lexeme = self.current_string.clone();
 
         let mut token_type = r#type.clone();
+        let mut is_out_of_range = false;
 
         let literal = match r#type {
             // We are using serde_json::from_str to parse the string,
@@ -236,7 +253,15 @@ impl Lexer {
                 _ => Some(Literal::String(lexeme.to_string())),
             },
             TokenType::Null => Some(Literal::Null(Null {})),
-            TokenType::Number => Some(Literal::Number(Number::from_str(&lexeme).unwrap())),
+            // Scanned digits can still overflow, e.g. `0xFFFFFFFFFFFFFFFFF`
+            TokenType::Number => match Number::from_str(&lexeme) {
+                Ok(number) => Some(Literal::Number(number)),
+                Err(_) => {
+                    token_type = TokenType::Unknown;
+                    is_out_of_range = true;
+                    None
+                }
+            },
             TokenType::Regex => {
                 let regex_value = lexeme[lexeme.chars().next().unwrap().len_utf8()
                     ..lexeme.len() - lexeme.chars().next_back().unwrap().len_utf8()]
@@ -251,12 +276,22 @@ impl Lexer {
             r#type: token_type,
             literal,
             range: Range {
-                start: self.start,
-                end: self.current - 1,
+                start: self.start_relative,
+                end: self.current_relative.saturating_sub(1),
             },
             line: self.line,
             lexeme: lexeme.to_string(),
         });
+
+        if is_out_of_range {
+            self.error(
+                &format!("Number {} is out of range", lexeme),
+                UnexpectedTokenError {
+                    expected: TokenType::Number,
+                    found: TokenType::Unknown,
+                },
+            );
+        }
     }
 
     fn is_espaced_char_or_espace(&mut self, c: char) -> bool {
@@ -265,10 +300,6 @@ impl Lexer {
 
     fn string(&mut self, str_variant: char) -> Result<(), ()> {
         while self.peek() != str_variant && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.current_relative = 0;
-            }
-
             if self.is_espaced_char_or_espace(str_variant) {
                 self.advance();
             }
@@ -293,10 +324,6 @@ impl Lexer {
 
     fn regex(&mut self) -> Result<(), ()> {
         while self.peek() != '/' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.current_relative = 0;
-            }
-
             if self.is_espaced_char_or_espace('/') {
                 self.advance();
             }
@@ -325,6 +352,22 @@ impl Lexer {
             self.advance();
         }
 
+        // Would be tokenized as an identifier right after the regex, e.g. `g` of `/a/g`
+        if self.is_identifier() {
+            self.error(
+                &format!(
+                    "Unsupported regex flag '{}', supported flags are {}",
+                    self.peek(),
+                    valid_regex_flags.iter().collect::<String>()
+                ),
+                UnexpectedTokenError {
+                    expected: TokenType::RegexFlags,
+                    found: TokenType::Unknown,
+                },
+            );
+            return Err(());
+        }
+
         Ok(())
     }
 
@@ -333,19 +376,61 @@ impl Lexer {
     }
 
     fn identifier(&mut self) -> Result<(), ()> {
-        while self.is_identifier() {
+        // Digits can't start an identifier, but can follow, e.g. `name_1`
+        while self.is_identifier() || self.peek().is_ascii_digit() {
             self.advance();
         }
 
         Ok(())
     }
 
+    /// Number follows a sign, either starting with a digit or a dot, e.g. `-5` or `-.5`
+    fn is_number_next(&self) -> bool {
+        self.peek().is_ascii_digit() || (self.peek() == '.' && self.peek_next().is_ascii_digit())
+    }
+
+    /// Last token ends a value, so a dot after it accesses its member
+    fn is_after_operand(&self) -> bool {
+        self.tokens.last().is_some_and(|token| {
+            matches!(
+                token.r#type,
+                TokenType::Identifier
+                    | TokenType::RightParen
+                    | TokenType::RightBracket
+                    | TokenType::RightBrace
+            )
+        })
+    }
+
     fn digit(&mut self) -> Result<(), ()> {
+        if matches!(self.current_string.as_str(), "-" | "+") {
+            if self.peek() == '.' {
+                self.advance();
+            }
+            self.advance();
+        }
+
+        if self.current_string.trim_start_matches(['-', '+']) == "0"
+            && matches!(self.peek(), 'x' | 'X')
+        {
+            self.advance();
+
+            if !self.peek().is_ascii_hexdigit() {
+                return Err(());
+            }
+            while self.peek().is_ascii_hexdigit() {
+                self.advance();
+            }
+
+            return Ok(());
+        }
+
         while self.peek().is_numeric() {
             self.advance();
         }
 
-        if self.peek() == '.' && self.peek_next().is_numeric() {
+        if !self.current_string.contains('.') && self.peek() == '.' && self.peek_next().is_numeric()
+        {
             self.advance();
 
             while self.peek().is_numeric() {
@@ -353,6 +438,21 @@ impl Lexer {
             }
         }
 
+        // Exponent part, e.g. 1e6, 1.5E-2
+        if matches!(self.peek(), 'e' | 'E')
+            && (self.peek_next().is_ascii_digit()
+                || (matches!(self.peek_next(), '+' | '-') && self.peek_nth(2).is_ascii_digit()))
+        {
+            self.advance();
+            if matches!(self.peek(), '+' | '-') {
+                self.advance();
+            }
+
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+        }
+
         Ok(())
     }
 
@@ -383,10 +483,14 @@ impl Lexer {
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
-            return '\0';
-        }
-        return self.source.chars().nth(self.current + 1).unwrap();
+        self.peek_nth(1)
+    }
+
+    fn peek_nth(&self, offset: usize) -> char {
+        self.source[self.current_in_bytes..]
+            .chars()
+            .nth(offset)
+            .unwrap_or('\0')
     }
 
     fn advance(&mut self) -> char {
@@ -396,7 +500,11 @@ impl Lexer {
         self.current_in_bytes += len;
         self.current += 1;
         self.current_string += &ch.to_string();
-        self.current_relative += len;
+        if ch == '\n' {
+            self.current_relative = 0;
+        } else {
+            self.current_relative += 1;
+        }
 
         ch
     }
@@ -78,6 +78,9 @@ pub struct Parser {
 #[derive(Debug)]
 pub struct ParseError {
     pub token_pos: usize,
+    /// Opening delimiter of an unterminated object, array or parameters expression, the error
+    /// spans from it up to `token_pos`
+    pub start_token_pos: Option<usize>,
     pub message: String,
     pub r#type: UnexpectedTokenError,
 }
@@ -139,6 +142,7 @@ impl Parser {
                 }
                 _ => Err(ParseError {
                     token_pos: self.current,
+                    start_token_pos: None,
                     message: format!("Expected identifier, got {:?}", self.peek()),
                     r#type: UnexpectedTokenError {
                         expected: TokenType::Identifier,
@@ -172,6 +176,7 @@ impl Parser {
             Some(_) => Ok(Identifier::Literal(self.advance()?.literal.unwrap())),
             None => Err(ParseError {
                 token_pos: self.current,
+                start_token_pos: None,
                 message: format!("Expected literal, got {:?}", self.peek()),
                 r#type: UnexpectedTokenError {
                     // Not entirely correct
@@ -205,6 +210,7 @@ impl Parser {
         if self.is_at_end() {
             return Err(ParseError {
                 token_pos: self.current.saturating_sub(1),
+                start_token_pos: None,
                 message: "Expected end of array expression".to_string(),
                 r#type: UnexpectedTokenError {
                     expected: TokenType::RightBracket,
@@ -219,7 +225,11 @@ impl Parser {
 
     fn regex_expression(&mut self) -> Result<Identifier, ParseError> {
         let regex = self.advance()?.literal.unwrap().to_string();
-        let flags = self.advance()?.literal.unwrap().to_string();
+        let flags = if !self.is_at_end() && self.check(TokenType::RegexFlags)? {
+            self.advance()?.literal.unwrap().to_string()
+        } else {
+            String::new()
+        };
 
         Ok(Identifier::Regex(RegexExpression { regex, flags }))
     }
@@ -231,8 +241,12 @@ impl Parser {
             | TokenType::String
             | TokenType::Bool
             | TokenType::Null => self.literal_expression().ok(),
-            TokenType::LeftBrace => Some(Identifier::Object(self.object_expression()?)),
-            TokenType::LeftBracket => Some(Identifier::Array(self.array_expression()?)),
+            TokenType::LeftBrace => {
+                Some(Identifier::Object(self.delimited(Self::object_expression)?))
+            }
+            TokenType::LeftBracket => {
+                Some(Identifier::Array(self.delimited(Self::array_expression)?))
+            }
             TokenType::Regex => Some(self.regex_expression()?),
             _ => None,
         };
@@ -241,6 +255,7 @@ impl Parser {
             Some(val) => Ok(val),
             None => Err(ParseError {
                 token_pos: self.current,
+                start_token_pos: None,
                 message: format!(
                     "Expected identifier expression, got {:?} instead",
                     self.peek(),
@@ -293,6 +308,7 @@ impl Parser {
         if self.is_at_end() && brackets != 0 {
             return Err(ParseError {
                 token_pos: self.current.saturating_sub(1),
+                start_token_pos: None,
                 message: "Unexpected end of object expression".to_string(),
                 r#type: UnexpectedTokenError {
                     expected: TokenType::RightBrace,
@@ -310,6 +326,7 @@ impl Parser {
         if self.is_at_end() {
             return Err(ParseError {
                 token_pos: self.current.saturating_sub(1),
+                start_token_pos: None,
                 message: "Expected ')'".to_string(),
                 r#type: UnexpectedTokenError {
                     expected: TokenType::RightParen,
@@ -329,6 +346,7 @@ impl Parser {
         if self.is_at_end() {
             return Err(ParseError {
                 token_pos: self.current.saturating_sub(1),
+                start_token_pos: None,
                 message: "Unexpected end of parameters expression".to_string(),
                 r#type: UnexpectedTokenError {
                     expected: TokenType::RightParen,
@@ -343,7 +361,7 @@ impl Parser {
 
     fn call_expression(&mut self, callee: Callee) -> Result<CallExpression, ParseError> {
         let primary = CallExpressionPrimary {
-            params: self.parameters_expression()?,
+            params: self.delimited(Self::parameters_expression)?,
             callee,
         };
         let recursive = self.call_expression_recursive(CallExpression::Primary(primary))?;
@@ -360,7 +378,7 @@ impl Parser {
         }
 
         if self.check(TokenType::LeftParen)? {
-            let params = self.parameters_expression()?;
+            let params = self.delimited(Self::parameters_expression)?;
             return self
                 .call_expression_recursive(CallExpression::Recursive(Box::new(base), params));
         }
@@ -409,6 +427,7 @@ impl Parser {
             true => Ok(token),
             false => Err(ParseError {
                 token_pos: self.current - 1,
+                start_token_pos: None,
                 message: format!("Expected {:?}, got {:?}", token_type, token),
                 r#type: UnexpectedTokenError {
                     expected: token_type,
@@ -418,6 +437,21 @@ impl Parser {
         }
     }
 
+    /// Runs `parse` on an expression starting with an opening delimiter, when the input ends
+    /// before the expression is closed, the error is extended to start at the delimiter.
+    fn delimited<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        let start = self.current;
+        parse(self).map_err(|mut err| {
+            if err.r#type.found == TokenType::Eof && err.start_token_pos.is_none() {
+                err.start_token_pos = Some(start);
+            }
+            err
+        })
+    }
+
     fn check(&self, token_type: TokenType) -> Result<bool, ParseError> {
         Ok(self.peek()?.r#type == token_type)
     }
@@ -440,6 +474,7 @@ impl Parser {
         if self.current + 1 >= self.tokens.len() {
             return Err(ParseError {
                 token_pos: self.current.saturating_sub(1),
+                start_token_pos: None,
                 message: "Unexpected end of program".to_string(),
                 r#type: UnexpectedTokenError {
                     expected: TokenType::Unknown,
@@ -454,6 +489,7 @@ impl Parser {
         if self.is_at_end() {
             return Err(ParseError {
                 token_pos: self.current.saturating_sub(1),
+                start_token_pos: None,
                 message: "Unexpected end of program".to_string(),
                 r#type: UnexpectedTokenError {
                     expected: TokenType::Unknown,
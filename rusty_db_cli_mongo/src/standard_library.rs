@@ -1,10 +1,42 @@
 use std::collections::HashMap;
 
+/// Collection methods understood by the mongodb connector, e.g. `db.users.find()`
+pub const COMMANDS: &[&str] = &[
+    "find",
+    "count",
+    "aggregate",
+    "distinct",
+    "getIndexes",
+    "createIndex",
+    "dropIndex",
+    "drop",
+    "stats",
+    "bulkWrite",
+    "watch",
+];
+
+/// Methods that can be chained after a collection method, e.g. `db.users.find().limit(10)`
+pub const SUB_COMMANDS: &[&str] = &[
+    "count",
+    "sort",
+    "allowDiskUse",
+    "explain",
+    "skip",
+    "limit",
+    "maxTimeMS",
+    "hint",
+    "readConcern",
+    "readPreference",
+    "project",
+    "tail",
+    "comment",
+];
+
 pub struct StandardLibrary {
     pub types: HashMap<String, TypeInfo>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TypeInfo {
     pub name: String,
     pub methods: Vec<MethodInfo>,
@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use bson::{oid::ObjectId, Bson, DateTime as BsonDateTime};
+use bson::{oid::ObjectId, Bson, DateTime as BsonDateTime, Decimal128};
 use chrono::{DateTime, NaiveDate, Utc};
 use dyn_clone::DynClone;
 use rusty_db_cli_derive_internals::{TryFrom, WithType};
@@ -9,7 +9,7 @@ use serde::{
     Serialize,
 };
 
-use super::literals::Literal;
+use super::literals::{Literal, Number};
 use crate::{
     interpreter::InterpreterError,
     parser::Expression,
@@ -188,6 +188,33 @@ impl Serialize for CallExpression {
 
                         ObjectId::from_str(&value).unwrap().serialize(serializer)
                     }
+                    "NumberDecimal" | "Decimal128" => {
+                        if call.params.params.len() > 1 {
+                            return Err(Error::custom(format!(
+                                "{} can only have one parameter",
+                                key
+                            )));
+                        }
+                        // Numbers are read by their shortest representation, e.g. 0.1 stays 0.1
+                        let value = match call.params.get_nth_of_type::<Literal>(0) {
+                            Ok(Literal::String(value)) => value,
+                            Ok(Literal::Number(Number::F64(value))) => value.to_string(),
+                            Ok(Literal::Number(Number::I64(value))) => value.to_string(),
+                            Ok(Literal::Number(Number::I32(value))) => value.to_string(),
+                            Ok(literal) => {
+                                return Err(Error::custom(format!(
+                                    "{} expects a string or a number, got {:?}",
+                                    key, literal
+                                )))
+                            }
+                            Err(err) => return Err(Error::custom(err.message)),
+                        };
+
+                        match Decimal128::from_str(&value) {
+                            Ok(decimal) => Bson::Decimal128(decimal).serialize(serializer),
+                            Err(err) => Err(Error::custom(err.to_string())),
+                        }
+                    }
                     _ => Err(Error::custom("Invalid primary call expression.")),
                 }
             }
@@ -1,4 +1,5 @@
 use std::{
+    cmp::Ordering,
     num::{ParseFloatError, ParseIntError},
     str::FromStr,
 };
@@ -35,13 +36,80 @@ impl Typed for bool {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Number {
     F64(f64),
     I64(i64),
     I32(i32),
 }
 
+impl Number {
+    /// Orders NaN after all other numbers, so numbers can be sorted
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other)
+            .unwrap_or_else(|| match (self.is_nan(), other.is_nan()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                _ => Ordering::Less,
+            })
+    }
+
+    fn is_nan(&self) -> bool {
+        matches!(self, Number::F64(v) if v.is_nan())
+    }
+
+    fn as_integer(&self) -> Option<i64> {
+        match self {
+            Number::F64(_) => None,
+            Number::I64(v) => Some(*v),
+            Number::I32(v) => Some(*v as i64),
+        }
+    }
+}
+
+/// Values are compared regardless of their type, e.g. `I32(1) == F64(1.0)`. NaN is not equal to
+/// anything, the same as for `f64`
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Number::F64(a), Number::F64(b)) => a.partial_cmp(b),
+            (Number::F64(a), _) => {
+                compare_integer_to_float(other.as_integer()?, *a).map(Ordering::reverse)
+            }
+            (_, Number::F64(b)) => compare_integer_to_float(self.as_integer()?, *b),
+            _ => Some(self.as_integer()?.cmp(&other.as_integer()?)),
+        }
+    }
+}
+
+/// Integers above 2^53 lose precision as floats, the float is split into its integer and
+/// fractional part instead
+fn compare_integer_to_float(integer: i64, float: f64) -> Option<Ordering> {
+    // Bounds of i64 are exactly representable as powers of two, `i64::MAX` is not
+    const I64_END: f64 = 9_223_372_036_854_775_808.0;
+    if float.is_nan() {
+        return None;
+    }
+    if float >= I64_END {
+        return Some(Ordering::Less);
+    }
+    if float < -I64_END {
+        return Some(Ordering::Greater);
+    }
+
+    let truncated = float.trunc();
+    Some(integer.cmp(&(truncated as i64)).then_with(|| {
+        0.0.partial_cmp(&(float - truncated))
+            .unwrap_or(Ordering::Equal)
+    }))
+}
+
 impl ToString for Number {
     fn to_string(&self) -> String {
         match self {
@@ -117,7 +185,22 @@ impl FromStr for Number {
     type Err = NumberParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.contains('.') {
+        let (sign, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if let Some(hex) = unsigned
+            .strip_prefix("0x")
+            .or_else(|| unsigned.strip_prefix("0X"))
+        {
+            let value = sign * i64::from_str_radix(hex, 16)?;
+            return Ok(match i32::try_from(value) {
+                Ok(v) => Number::I32(v),
+                Err(_) => Number::I64(value),
+            });
+        }
+
+        if s.contains(['.', 'e', 'E']) {
             match s.parse::<f64>() {
                 Ok(v) => Ok(Number::F64(v)),
                 Err(e) => Err(NumberParseError::from(e)),
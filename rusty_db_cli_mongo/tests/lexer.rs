@@ -0,0 +1,113 @@
+use std::mem::discriminant;
+
+use rusty_db_cli_mongo::{
+    lexer::{Lexer, TokenType},
+    types::literals::{Literal, Number},
+};
+
+fn scan_number(source: &str) -> Number {
+    let tokens = Lexer::new(source.to_string()).scan_tokens().unwrap();
+    assert_eq!(tokens.len(), 1, "Expected single token, got {:?}", tokens);
+
+    match tokens[0].literal.clone() {
+        Some(Literal::Number(number)) => number,
+        literal => panic!("Expected number, got {:?}", literal),
+    }
+}
+
+/// Numbers of different types are equal, the type is checked as well
+fn assert_number(source: &str, expected: Number) {
+    let number = scan_number(source);
+    assert_eq!(number, expected);
+    assert_eq!(
+        discriminant(&number),
+        discriminant(&expected),
+        "{:?}",
+        number
+    );
+}
+
+#[test]
+fn signed_numbers() {
+    assert_number("-0.5", Number::F64(-0.5));
+    assert_number("-5", Number::I32(-5));
+    assert_number("+5", Number::I32(5));
+    assert_number("+0x10", Number::I32(16));
+}
+
+#[test]
+fn leading_dot_floats() {
+    assert_number(".5", Number::F64(0.5));
+    assert_number("-.5", Number::F64(-0.5));
+    assert_number("+.5e1", Number::F64(5.0));
+}
+
+#[test]
+fn dot_after_identifier_is_member_access() {
+    let types = Lexer::new("db.c".to_string())
+        .scan_tokens()
+        .unwrap()
+        .into_iter()
+        .map(|token| token.r#type)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        types,
+        vec![TokenType::Identifier, TokenType::Dot, TokenType::Identifier]
+    );
+}
+
+#[test]
+fn identifier_with_digits() {
+    let tokens = Lexer::new("name_1".to_string()).scan_tokens().unwrap();
+
+    assert_eq!(tokens.len(), 1, "Expected single token, got {:?}", tokens);
+    assert_eq!(tokens[0].r#type, TokenType::Identifier);
+    assert!(
+        matches!(&tokens[0].literal, Some(Literal::String(name)) if name == "name_1"),
+        "Expected identifier name_1, got {:?}",
+        tokens[0].literal
+    );
+}
+
+#[test]
+fn exponent_and_hex_numbers() {
+    assert_number("1e3", Number::F64(1000.0));
+    assert_number("1E3", Number::F64(1000.0));
+    assert_number("1.5e-2", Number::F64(0.015));
+    assert_number("0xFF", Number::I32(255));
+    assert_number("0xff", Number::I32(255));
+}
+
+#[test]
+fn out_of_range_numbers_are_errors() {
+    for source in ["0xFFFFFFFFFFFFFFFFF", "99999999999999999999"] {
+        let (tokens, errors) = Lexer::new(source.to_string()).scan_tokens().unwrap_err();
+
+        assert_eq!(tokens[0].r#type, TokenType::Unknown);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(
+            errors[0].message,
+            format!("Number {} is out of range", source)
+        );
+    }
+}
+
+#[test]
+fn ranges_count_characters() {
+    let tokens = Lexer::new("db.c.find({a: \"čau\", b: 1})".to_string())
+        .scan_tokens()
+        .unwrap();
+    let range = |lexeme: &str| {
+        tokens
+            .iter()
+            .find(|token| token.lexeme() == lexeme)
+            .map(|token| (token.range.start, token.range.end))
+            .unwrap()
+    };
+
+    // `č` takes two bytes, the tokens after it are still placed by characters
+    assert_eq!(range("\"čau\""), (14, 18));
+    assert_eq!(range(","), (19, 19));
+    assert_eq!(range("1"), (24, 24));
+}
@@ -0,0 +1,49 @@
+use std::cmp::Ordering;
+
+use rusty_db_cli_mongo::types::literals::Number;
+
+#[test]
+fn numbers_are_equal_across_types() {
+    assert_eq!(Number::I32(1), Number::I64(1));
+    assert_eq!(Number::I64(1), Number::F64(1.0));
+    assert_eq!(Number::F64(-0.0), Number::I32(0));
+    assert_ne!(Number::I32(1), Number::F64(1.5));
+    assert_ne!(Number::F64(f64::NAN), Number::F64(f64::NAN));
+}
+
+#[test]
+fn numbers_are_ordered_across_types() {
+    assert!(Number::I32(2) < Number::F64(2.5));
+    assert!(Number::F64(-5.5) < Number::I64(-5));
+    assert!(Number::I64(i64::MIN) > Number::F64(f64::NEG_INFINITY));
+    assert_eq!(Number::I32(1).partial_cmp(&Number::F64(f64::NAN)), None);
+}
+
+#[test]
+fn large_integers_keep_precision() {
+    // 2^53 + 1 is rounded to 2^53 as a float
+    assert!(Number::I64(9_007_199_254_740_993) > Number::F64(9_007_199_254_740_992.0));
+    assert!(Number::I64(i64::MAX) < Number::F64(i64::MAX as f64));
+    assert_eq!(Number::I64(i64::MIN), Number::F64(i64::MIN as f64));
+}
+
+#[test]
+fn nan_is_sorted_last() {
+    let mut numbers = vec![
+        Number::F64(f64::NAN),
+        Number::F64(2.5),
+        Number::I64(-3),
+        Number::I32(2),
+        Number::F64(f64::NEG_INFINITY),
+    ];
+    numbers.sort_by(Number::total_cmp);
+
+    assert_eq!(
+        format!("{:?}", numbers),
+        "[F64(-inf), I64(-3), I32(2), F64(2.5), F64(NaN)]"
+    );
+    assert_eq!(
+        Number::F64(f64::NAN).total_cmp(&Number::F64(f64::NAN)),
+        Ordering::Equal
+    );
+}
@@ -0,0 +1,145 @@
+use rusty_db_cli_mongo::{
+    lexer::{Lexer, TokenType},
+    parser::{Expression, Parser},
+    types::expressions::{CallExpression, Identifier},
+};
+
+fn parse_first_param(query: &str) -> Identifier {
+    let tokens = Lexer::new(query.to_string()).scan_tokens().unwrap();
+    let mut program = Parser::new(tokens).parse().unwrap();
+
+    match program.body.remove(0) {
+        Expression::ExpressionStatement(statement) => match statement.expression {
+            CallExpression::Primary(call) => call.params.params[0].clone(),
+            expression => panic!("Expected call expression, got {:?}", expression),
+        },
+        expression => panic!("Expected expression statement, got {:?}", expression),
+    }
+}
+
+#[test]
+fn object_trailing_comma() {
+    match parse_first_param("db.c.find({a: 1, b: 2,})") {
+        Identifier::Object(object) => assert_eq!(object.properties.len(), 2),
+        identifier => panic!("Expected object, got {:?}", identifier),
+    }
+}
+
+#[test]
+fn array_trailing_comma() {
+    match parse_first_param("db.c.aggregate([{a: 1}, {b: 2},])") {
+        Identifier::Array(array) => assert_eq!(array.elements.len(), 2),
+        identifier => panic!("Expected array, got {:?}", identifier),
+    }
+}
+
+#[test]
+fn unterminated_object_spans_from_opening_brace() {
+    let tokens = Lexer::new("db.c.find({a: 1".to_string())
+        .scan_tokens()
+        .unwrap();
+    let error = Parser::new(tokens.clone()).parse().unwrap_err();
+
+    let start = error.start_token_pos.expect("Expected error span");
+    assert_eq!(tokens[start].r#type, TokenType::LeftBrace);
+    assert!(error.token_pos > start);
+}
+
+#[test]
+fn regex_with_flags_in_object() {
+    let identifier = parse_first_param("db.c.find({name: /^a/i})");
+
+    assert_eq!(
+        bson::to_bson(&identifier).unwrap(),
+        bson::Bson::Document(bson::doc! {
+            "name": bson::Regex {
+                pattern: "^a".to_string(),
+                options: "i".to_string(),
+            }
+        })
+    );
+}
+
+#[test]
+fn regex_without_flags_in_object() {
+    let identifier = parse_first_param("db.c.find({name: /^a/, age: 1})");
+
+    assert_eq!(
+        bson::to_bson(&identifier).unwrap(),
+        bson::Bson::Document(bson::doc! {
+            "name": bson::Regex {
+                pattern: "^a".to_string(),
+                options: String::new(),
+            },
+            "age": 1,
+        })
+    );
+}
+
+#[test]
+fn regex_with_unsupported_flag() {
+    let (_, errors) = Lexer::new("db.c.find({a: /x/g})".to_string())
+        .scan_tokens()
+        .unwrap_err();
+
+    assert!(errors[0].message.contains("Unsupported regex flag 'g'"));
+}
+
+#[test]
+fn negative_numbers_in_nested_expressions() {
+    let identifier =
+        parse_first_param("db.c.aggregate([{$project: {x: {$subtract: [0, -1]}, y: -.5}}])");
+
+    assert_eq!(
+        bson::to_bson(&identifier).unwrap(),
+        bson::Bson::Array(vec![bson::Bson::Document(bson::doc! {
+            "$project": {"x": {"$subtract": [0, -1]}, "y": -0.5}
+        })])
+    );
+}
+
+#[test]
+fn number_decimal_round_trip() {
+    let decimal = |value: &str| {
+        bson::to_bson(&parse_first_param(&format!("db.c.find({{a: {}}})", value)))
+            .map(|filter| filter.as_document().unwrap().get("a").cloned())
+    };
+
+    for (value, expected) in [
+        ("NumberDecimal('1.10')", "1.10"),
+        ("NumberDecimal(1.5)", "1.5"),
+        ("Decimal128(-3)", "-3"),
+    ] {
+        match decimal(value).unwrap() {
+            Some(bson::Bson::Decimal128(decimal)) => assert_eq!(decimal.to_string(), expected),
+            value => panic!("Expected decimal, got {:?}", value),
+        }
+    }
+    assert!(decimal("NumberDecimal()").is_err());
+    assert!(decimal("NumberDecimal('abc')").is_err());
+    assert!(decimal("NumberDecimal(true)").is_err());
+}
+
+#[test]
+fn regex_literals() {
+    let regex = |query: &str| match bson::to_bson(&parse_first_param(query)).unwrap() {
+        bson::Bson::Document(filter) => match filter.get("a") {
+            Some(bson::Bson::RegularExpression(regex)) => regex.clone(),
+            value => panic!("Expected regex, got {:?}", value),
+        },
+        value => panic!("Expected document, got {:?}", value),
+    };
+    let expected = |pattern: &str, options: &str| bson::Regex {
+        pattern: pattern.to_string(),
+        options: options.to_string(),
+    };
+
+    assert_eq!(regex("db.c.find({a: /abc/})"), expected("abc", ""));
+    assert_eq!(regex("db.c.find({a: /abc/i})"), expected("abc", "i"));
+    // Escaped slash doesn't end the pattern, the escape is kept for the server
+    assert_eq!(regex(r"db.c.find({a: /a\/b/})"), expected(r"a\/b", ""));
+    assert_eq!(
+        regex(r"db.c.find({a: /a\/b/m, b: 1})"),
+        expected(r"a\/b", "m")
+    );
+}